@@ -0,0 +1,722 @@
+/// A module to model the (rectangular) area the balls bounce in.
+extern crate nannou;
+use nannou::prelude::*;
+
+use crate::ball::Ball;
+
+// upper limit of hits resolved within one update (e.g. ball stuck in a corner)
+const MAX_HITS_PER_UPDATE: usize = 16;
+// closing speed (pixels per update) on top of what gravity adds within an update,
+// below which a ball pressed onto a wall by gravity rests on it instead of hitting it
+const RESTING_SPEED: f32 = 0.05;
+// distance (pixels) from a wall within which a ball still touches it
+const RESTING_GAP: f32 = 0.01;
+
+/// The four walls of the arena.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Wall {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl Wall {
+    pub const ALL: [Wall; 4] = [Wall::Top, Wall::Right, Wall::Bottom, Wall::Left];
+
+    // Position of the wall in per-wall arrays.
+    pub fn index(&self) -> usize {
+        match self {
+            Wall::Top => 0,
+            Wall::Right => 1,
+            Wall::Bottom => 2,
+            Wall::Left => 3,
+        }
+    }
+
+    // Human readable name of the wall.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Wall::Top => "top",
+            Wall::Right => "right",
+            Wall::Bottom => "bottom",
+            Wall::Left => "left",
+        }
+    }
+
+    // Find a wall by its name.
+    pub fn from_name(name: &str) -> Option<Wall> {
+        Wall::ALL.iter().find(|w| w.name() == name).copied()
+    }
+
+    // Normal vector pointing out of the arena (in arena coordinates).
+    pub fn normal(&self) -> Point2 {
+        match self {
+            Wall::Top => pt2(0.0, 1.0),
+            Wall::Right => pt2(1.0, 0.0),
+            Wall::Bottom => pt2(0.0, -1.0),
+            Wall::Left => pt2(-1.0, 0.0),
+        }
+    }
+
+    // Direction along the wall (normal rotated counter-clockwise).
+    pub fn tangent(&self) -> Point2 {
+        let n = self.normal();
+        pt2(-n.y, n.x)
+    }
+
+    // The wall on the other side of the arena.
+    pub fn opposite(&self) -> Wall {
+        match self {
+            Wall::Top => Wall::Bottom,
+            Wall::Right => Wall::Left,
+            Wall::Bottom => Wall::Top,
+            Wall::Left => Wall::Right,
+        }
+    }
+}
+
+/// What happens to a ball reaching a wall.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WallBehaviour {
+    Reflect, // bounce off
+    Wrap,    // re-appear at the opposite wall (torus)
+    Absorb,  // stop the ball
+    Portal,  // re-appear at the paired wall
+}
+
+impl WallBehaviour {
+    pub const ALL: [WallBehaviour; 4] = [
+        WallBehaviour::Reflect,
+        WallBehaviour::Wrap,
+        WallBehaviour::Absorb,
+        WallBehaviour::Portal,
+    ];
+
+    // Human readable name of the behaviour.
+    pub fn name(&self) -> &'static str {
+        match self {
+            WallBehaviour::Reflect => "reflect",
+            WallBehaviour::Wrap => "wrap",
+            WallBehaviour::Absorb => "absorb",
+            WallBehaviour::Portal => "portal",
+        }
+    }
+}
+
+/// Where a portal leads to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Portal {
+    pub target: Wall,  // wall to exit from
+    pub rotation: f32, // additional rotation of the velocity (degrees)
+}
+
+/// What happened when the ball reached a wall.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HitKind {
+    Bounce,
+    Wrap,
+    Absorb,
+    Teleport,
+}
+
+/// A ball reaching a wall.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    pub wall: Wall,
+    pub kind: HitKind,
+    pub time: f32, // when the wall was reached (in updates, e.g. 0.5 = halfway through)
+    pub position: Point2, // where the ball touched the wall (arena coordinates)
+}
+
+/// A periodic movement, either free running or synced to the tempo.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Motion {
+    pub amplitude: f32, // maximum displacement (in pixels, unused for rotation)
+    pub rate: f32,      // cycles per second (or per beat when synced)
+    pub synced: bool,   // interpret rate as cycles per beat
+}
+
+impl Motion {
+    // Create a motion which does not move at all.
+    pub fn still() -> Motion {
+        Motion {
+            amplitude: 0.0,
+            rate: 0.0,
+            synced: false,
+        }
+    }
+
+    // Number of cycles completed after t seconds.
+    pub fn cycles(&self, t: f32, bpm: f32) -> f32 {
+        if self.synced {
+            return self.rate * t * bpm / 60.0;
+        }
+        self.rate * t
+    }
+}
+
+/// The (rectangular) arena with optionally rotating and oscillating walls.
+#[derive(Clone)]
+pub struct Arena {
+    pub center: Point2,
+    pub width: f32,
+    pub height: f32,
+    pub rotation: Motion,               // rotation of the whole arena
+    pub wall_motions: [Motion; 4],      // oscillation of the individual walls
+    pub restitution: [f32; 4],          // fraction of the speed kept when bouncing off a wall
+    pub behaviours: [WallBehaviour; 4], // what happens at each wall
+    pub portals: [Portal; 4],           // exits of the walls in portal mode
+    pub gravity: Point2,                // pressing balls onto the walls (world, pixels per frame²)
+    angle: f32,                         // current rotation angle (radians)
+    angular_velocity: f32,              // change of angle per frame
+    phases: [f32; 4],                   // current phase of the wall oscillations (cycles)
+    offsets: [f32; 4],                  // current displacement of the walls (along the normal)
+    offset_velocities: [f32; 4],        // change of displacement per frame
+}
+
+impl Arena {
+    // Create a still arena.
+    pub fn new(center: Point2, width: f32, height: f32) -> Arena {
+        Arena {
+            center,
+            width,
+            height,
+            rotation: Motion::still(),
+            wall_motions: [Motion::still(); 4],
+            restitution: [1.0; 4], // perfectly elastic
+            behaviours: [WallBehaviour::Reflect; 4],
+            portals: [
+                Portal {
+                    target: Wall::Bottom,
+                    rotation: 0.0,
+                },
+                Portal {
+                    target: Wall::Left,
+                    rotation: 0.0,
+                },
+                Portal {
+                    target: Wall::Top,
+                    rotation: 0.0,
+                },
+                Portal {
+                    target: Wall::Right,
+                    rotation: 0.0,
+                },
+            ],
+            gravity: pt2(0.0, 0.0),
+            angle: 0.0,
+            angular_velocity: 0.0,
+            phases: [0.0; 4],
+            offsets: [0.0; 4],
+            offset_velocities: [0.0; 4],
+        }
+    }
+
+    // Move the walls on by dt seconds. Every motion advances its own phase,
+    // so a changed rate (or tempo) changes the speed, not the position.
+    // Velocities are tracked per update (frame), like the ball velocity.
+    pub fn update(&mut self, dt: f32, bpm: f32) {
+        self.angular_velocity = self.rotation.cycles(dt, bpm) * 2.0 * PI;
+        self.angle = (self.angle + self.angular_velocity).rem_euclid(2.0 * PI);
+        for wall in Wall::ALL.iter() {
+            let i = wall.index();
+            let m = self.wall_motions[i];
+            let step = m.cycles(dt, bpm);
+            self.phases[i] = (self.phases[i] + step).rem_euclid(1.0);
+            let phase = self.phases[i] * 2.0 * PI;
+            self.offsets[i] = m.amplitude * phase.sin();
+            self.offset_velocities[i] = m.amplitude * phase.cos() * step * 2.0 * PI;
+        }
+    }
+
+    // Copy of the arena at its current position with all walls standing still.
+    pub fn frozen(&self) -> Arena {
+        let mut arena = self.clone();
+        arena.angular_velocity = 0.0;
+        arena.offset_velocities = [0.0; 4];
+        arena
+    }
+
+    // Current rotation angle in radians.
+    pub fn get_angle(&self) -> f32 {
+        self.angle
+    }
+
+    // Current displacement of a wall.
+    pub fn get_offset(&self, wall: Wall) -> f32 {
+        self.offsets[wall.index()]
+    }
+
+    // Half the length of a wall (in arena coordinates).
+    pub fn half_length(&self, wall: Wall) -> f32 {
+        match wall {
+            Wall::Top | Wall::Bottom => {
+                (self.distance(Wall::Left) + self.distance(Wall::Right)) / 2.0
+            }
+            Wall::Right | Wall::Left => {
+                (self.distance(Wall::Top) + self.distance(Wall::Bottom)) / 2.0
+            }
+        }
+    }
+
+    // Distance of a wall from the center (in arena coordinates).
+    pub fn distance(&self, wall: Wall) -> f32 {
+        let half = match wall {
+            Wall::Top | Wall::Bottom => self.height / 2.0,
+            Wall::Right | Wall::Left => self.width / 2.0,
+        };
+        half + self.offsets[wall.index()]
+    }
+
+    // Convert a world position into arena coordinates.
+    pub fn to_local(&self, p: Point2) -> Point2 {
+        rotate(p - self.center, -self.angle)
+    }
+
+    // Convert a position in arena coordinates into the world.
+    pub fn to_world(&self, p: Point2) -> Point2 {
+        rotate(p, self.angle) + self.center
+    }
+
+    // The corners of the arena (world coordinates), counter-clockwise
+    // starting at the top right.
+    pub fn corners(&self) -> [Point2; 4] {
+        let t = self.distance(Wall::Top);
+        let r = self.distance(Wall::Right);
+        let b = -self.distance(Wall::Bottom);
+        let l = -self.distance(Wall::Left);
+        [
+            self.to_world(pt2(r, t)),
+            self.to_world(pt2(l, t)),
+            self.to_world(pt2(l, b)),
+            self.to_world(pt2(r, b)),
+        ]
+    }
+
+    // Check if a ball lies on a reflecting or absorbing wall gravity presses it
+    // onto: touching it and not leaving it faster than gravity pulls it back.
+    pub fn supports(&self, ball: &Ball) -> bool {
+        let p = self.to_local(ball.get_position());
+        let v = rotate(ball.get_velocity(), -self.angle);
+        let g = rotate(self.gravity, -self.angle);
+        Wall::ALL.iter().any(|wall| {
+            let n = wall.normal();
+            let pressing = g.x * n.x + g.y * n.y;
+            let gap = self.distance(*wall) - ball.get_radius() - (p.x * n.x + p.y * n.y);
+            let leaving = self.wall_speed(*wall, p) - (v.x * n.x + v.y * n.y);
+            let stops = match self.behaviours[wall.index()] {
+                WallBehaviour::Reflect | WallBehaviour::Absorb => true,
+                _ => false,
+            };
+            stops && pressing > 0.0 && gap < RESTING_GAP && leaving < RESTING_SPEED + pressing
+        })
+    }
+
+    // Velocity of a wall (along its normal) at a point in arena coordinates.
+    fn wall_speed(&self, wall: Wall, p: Point2) -> f32 {
+        // tangential velocity of the rotation: omega x r
+        let w = self.angular_velocity;
+        let spin = pt2(-w * p.y, w * p.x);
+        let n = wall.normal();
+        spin.x * n.x + spin.y * n.y + self.offset_velocities[wall.index()]
+    }
+
+    // Move the ball through one update (frame) and let it interact with the
+    // walls on its way. The time of contact is calculated for every hit (swept
+    // circle), so a fast ball can not tunnel through a wall and several hits
+    // within one update are reported in the order they happen.
+    pub fn advance(&self, ball: &mut Ball) -> Vec<Hit> {
+        self.sweep(ball, 1.0, MAX_HITS_PER_UPDATE)
+    }
+
+    // Move the ball for the given number of updates, stop early once max_hits
+    // walls were reached. The walls stay where they are during the sweep.
+    // A ball pressed by gravity onto a reflecting or absorbing wall, which
+    // reaches it slower than gravity accelerates it within an update (e.g.
+    // lying on the floor), stays on it without a hit.
+    pub fn sweep(&self, ball: &mut Ball, duration: f32, max_hits: usize) -> Vec<Hit> {
+        let mut hits = Vec::<Hit>::new();
+        let radius = ball.get_radius();
+        let mut p = self.to_local(ball.get_position());
+        let mut v = rotate(ball.get_velocity(), -self.angle);
+        let mut time = 0.0; // time passed within this update
+        let mut resting = [false; 4]; // walls the ball rests on
+        let g = rotate(self.gravity, -self.angle);
+
+        while hits.len() < max_hits {
+            // find the earliest contact within the rest of the update
+            let mut next: Option<(Wall, f32)> = None;
+            for wall in Wall::ALL.iter() {
+                if resting[wall.index()] {
+                    continue;
+                }
+                let n = wall.normal();
+                let closing = v.x * n.x + v.y * n.y - self.wall_speed(*wall, p);
+                if closing <= 0.0 {
+                    continue; // moving away from (or along) the wall
+                }
+                let gap = self.distance(*wall) - radius - (p.x * n.x + p.y * n.y);
+                let t = (gap / closing).max(0.0); // already touching: right now
+                let earlier = match next {
+                    Some((_, t_next)) => t < t_next,
+                    None => true,
+                };
+                if time + t <= duration && earlier {
+                    next = Some((*wall, t));
+                }
+            }
+            let (wall, t) = match next {
+                Some(contact) => contact,
+                None => break,
+            };
+
+            p = p + v * t; // move to the point of contact
+            time += t;
+            let n = wall.normal();
+            let closing = v.x * n.x + v.y * n.y - self.wall_speed(wall, p);
+            let stops = match self.behaviours[wall.index()] {
+                WallBehaviour::Reflect | WallBehaviour::Absorb => true,
+                _ => false,
+            };
+            let pressing = g.x * n.x + g.y * n.y; // gravity towards the wall
+            if stops && pressing > 0.0 && closing < RESTING_SPEED + pressing {
+                // move along with the wall
+                v = v - n * closing;
+                resting[wall.index()] = true;
+                continue;
+            }
+            let (p_new, v_new, kind) = self.resolve(wall, p, v, radius);
+            hits.push(Hit {
+                wall,
+                kind,
+                time,
+                position: p,
+            });
+            p = p_new;
+            v = v_new;
+        }
+        p = p + v * (duration - time); // rest of the sweep
+
+        ball.set_position(self.to_world(p));
+        ball.set_velocity(rotate(v, self.angle));
+        hits
+    }
+
+    // Let a ball touching a wall interact with it (arena coordinates).
+    // The velocity of a moving wall is transferred to a reflected ball, the
+    // speed relative to the wall is reduced by the restitution of the wall.
+    fn resolve(&self, wall: Wall, p: Point2, v: Point2, radius: f32) -> (Point2, Point2, HitKind) {
+        let n = wall.normal();
+        // put ball exactly onto the wall
+        let depth = p.x * n.x + p.y * n.y - (self.distance(wall) - radius);
+        let p_wall = p - n * depth;
+
+        match self.behaviours[wall.index()] {
+            WallBehaviour::Reflect => {
+                let wall_speed = self.wall_speed(wall, p_wall);
+                let v_n = v.x * n.x + v.y * n.y;
+                let e = self.restitution[wall.index()];
+                let v_n_new = wall_speed - e * (v_n - wall_speed);
+                (p_wall, v + n * (v_n_new - v_n), HitKind::Bounce)
+            }
+            WallBehaviour::Absorb => (p_wall, pt2(0.0, 0.0), HitKind::Absorb),
+            WallBehaviour::Wrap => {
+                let (p_new, v_new) = self.pass(wall, wall.opposite(), 0.0, p_wall, v, radius);
+                (p_new, v_new, HitKind::Wrap)
+            }
+            WallBehaviour::Portal => {
+                let portal = self.portals[wall.index()];
+                let (p_new, v_new) =
+                    self.pass(wall, portal.target, portal.rotation, p_wall, v, radius);
+                (p_new, v_new, HitKind::Teleport)
+            }
+        }
+    }
+
+    // Move a ball leaving through one wall to where it enters through another one.
+    // Position and velocity are mapped as if the exit wall was rotated onto the
+    // opposite of the entry wall, then the velocity gets an additional rotation.
+    fn pass(
+        &self,
+        entry: Wall,
+        exit: Wall,
+        rotation: f32,
+        p: Point2,
+        v: Point2,
+        radius: f32,
+    ) -> (Point2, Point2) {
+        let (n_in, t_in) = (entry.normal(), entry.tangent());
+        let (n_out, t_out) = (exit.normal(), exit.tangent());
+
+        // relative position along the entry wall (-1 ... 1)
+        let along = (p.x * t_in.x + p.y * t_in.y) / self.half_length(entry);
+        let along_out = -along * self.half_length(exit);
+        let p_new = n_out * (self.distance(exit) - radius) + t_out * along_out;
+
+        let v_n = v.x * n_in.x + v.y * n_in.y;
+        let v_t = v.x * t_in.x + v.y * t_in.y;
+        let mut v_new = rotate(n_out * -v_n - t_out * v_t, rotation.to_radians());
+        let v_out = v_new.x * n_out.x + v_new.y * n_out.y;
+        if v_out > 0.0 {
+            v_new = v_new - n_out * (2.0 * v_out); // never leave right away again
+        }
+        (p_new, v_new)
+    }
+}
+
+/// Rotate a vector by the given angle (radians).
+pub fn rotate(v: Point2, angle: f32) -> Point2 {
+    let (s, c) = angle.sin_cos();
+    pt2(v.x * c - v.y * s, v.x * s + v.y * c)
+}
+
+#[cfg(test)]
+mod arena_test {
+    use super::*;
+
+    fn ball_at(p: Point2, v: Point2) -> Ball {
+        let mut b = Ball::new();
+        b.set_radius(10.0);
+        b.set_position(p);
+        b.set_velocity(v);
+        b
+    }
+
+    #[test]
+    fn still_arena() {
+        let a = Arena::new(pt2(100.0, 0.0), 200.0, 100.0);
+        assert_eq!(a.distance(Wall::Right), 100.0);
+        assert_eq!(a.distance(Wall::Top), 50.0);
+        assert_eq!(a.to_local(pt2(150.0, 20.0)), pt2(50.0, 20.0));
+    }
+
+    fn close(a: Point2, b: Point2) -> bool {
+        (a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4
+    }
+
+    #[test]
+    fn reflect_on_static_wall() {
+        let a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        let mut b = ball_at(pt2(88.0, 0.0), pt2(3.0, 1.0));
+
+        let hits = a.advance(&mut b);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].wall, Wall::Right);
+        assert_eq!(hits[0].kind, HitKind::Bounce);
+        assert!((hits[0].time - 2.0 / 3.0).abs() < 1e-5);
+        assert!(close(hits[0].position, pt2(90.0, 2.0 / 3.0))); // the point of contact
+        assert_eq!(b.get_velocity(), pt2(-3.0, 1.0));
+        assert!(close(b.get_position(), pt2(89.0, 1.0)));
+    }
+
+    #[test]
+    fn no_hit() {
+        let a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        let mut b = ball_at(pt2(0.0, 0.0), pt2(3.0, 1.0));
+
+        assert!(a.advance(&mut b).is_empty());
+        assert_eq!(b.get_position(), pt2(3.0, 1.0));
+    }
+
+    #[test]
+    fn no_double_hit() {
+        let a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        let mut b = ball_at(pt2(90.0, 0.0), pt2(-3.0, 0.0));
+
+        // ball is touching the wall, but already moving away from it
+        assert!(a.advance(&mut b).is_empty());
+        assert_eq!(b.get_velocity(), pt2(-3.0, 0.0));
+    }
+
+    #[test]
+    fn no_tunnelling() {
+        let a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        let mut b = ball_at(pt2(0.0, 0.0), pt2(500.0, 0.0));
+
+        let hits = a.advance(&mut b);
+        let walls: Vec<Wall> = hits.iter().map(|hit| hit.wall).collect();
+        assert_eq!(walls, vec![Wall::Right, Wall::Left, Wall::Right]);
+        assert!((hits[0].time - 0.16).abs() < 1e-5);
+        assert!((hits[1].time - 0.52).abs() < 1e-5);
+        assert!((hits[2].time - 0.88).abs() < 1e-5);
+        assert!(close(b.get_position(), pt2(30.0, 0.0)));
+        assert_eq!(b.get_velocity(), pt2(-500.0, 0.0));
+    }
+
+    #[test]
+    fn corner() {
+        let a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        let mut b = ball_at(pt2(88.0, 88.0), pt2(4.0, 4.0));
+
+        let hits = a.advance(&mut b);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(b.get_velocity(), pt2(-4.0, -4.0));
+        assert!(close(b.get_position(), pt2(88.0, 88.0)));
+    }
+
+    #[test]
+    fn moving_wall_adds_velocity() {
+        let mut a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        a.wall_motions[Wall::Left.index()] = Motion {
+            amplitude: -20.0,
+            rate: 0.25,
+            synced: false,
+        };
+        a.update(0.1, 120.0); // left wall moves inwards
+
+        let mut b = ball_at(pt2(-88.0, 0.0), pt2(-2.0, 0.0));
+        let hits = a.advance(&mut b);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].wall, Wall::Left);
+        assert!(b.get_velocity().x > 2.0);
+    }
+
+    #[test]
+    fn restitution() {
+        let mut a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        a.restitution[Wall::Bottom.index()] = 0.5;
+        let mut b = ball_at(pt2(0.0, -88.0), pt2(1.0, -4.0));
+
+        assert_eq!(a.advance(&mut b)[0].wall, Wall::Bottom);
+        assert_eq!(b.get_velocity(), pt2(1.0, 2.0));
+    }
+
+    #[test]
+    fn resting_contact() {
+        let mut a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        a.restitution[Wall::Bottom.index()] = 0.5;
+        let gravity = 0.5;
+        a.gravity = pt2(0.0, -gravity);
+        let mut b = ball_at(pt2(0.0, 0.0), pt2(0.0, 0.0));
+
+        let mut bounces = vec![];
+        for frame in 0..600 {
+            b.set_velocity(b.get_velocity() + pt2(0.0, -gravity));
+            if !a.advance(&mut b).is_empty() {
+                bounces.push(frame);
+            }
+        }
+        assert!(bounces.len() > 2); // bounced some times
+        assert!(*bounces.last().unwrap() < 100); // then came to rest
+        assert!(close(b.get_position(), pt2(0.0, -90.0)));
+        assert_eq!(b.get_velocity(), pt2(0.0, 0.0));
+        assert!(a.supports(&b));
+        b.set_position(pt2(0.0, -80.0)); // in the air
+        assert!(!a.supports(&b));
+        b.set_position(pt2(0.0, -90.0));
+
+        // held against an absorbing wall
+        a.behaviours[Wall::Bottom.index()] = WallBehaviour::Absorb;
+        b.set_velocity(pt2(0.0, -gravity));
+        assert!(a.advance(&mut b).is_empty());
+    }
+
+    #[test]
+    fn slow_ball() {
+        // without gravity even a very slow ball bounces
+        let mut a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        let mut b = ball_at(pt2(89.99, 0.0), pt2(0.02, 0.0));
+        assert_eq!(a.advance(&mut b).len(), 1);
+        assert!(close(b.get_velocity(), pt2(-0.02, 0.0)));
+
+        // gravity presses onto the floor only, not onto the side walls
+        a.gravity = pt2(0.0, -0.5);
+        let mut b = ball_at(pt2(89.99, 0.0), pt2(0.02, 0.0));
+        assert_eq!(a.advance(&mut b)[0].wall, Wall::Right);
+        assert!(close(b.get_velocity(), pt2(-0.02, 0.0)));
+    }
+
+    #[test]
+    fn wrap() {
+        let mut a = Arena::new(pt2(0.0, 0.0), 200.0, 100.0);
+        a.behaviours[Wall::Right.index()] = WallBehaviour::Wrap;
+        let mut b = ball_at(pt2(88.0, 20.0), pt2(3.0, 1.0));
+
+        let hits = a.advance(&mut b);
+        assert_eq!(hits[0].kind, HitKind::Wrap);
+        assert!(close(hits[0].position, pt2(90.0, 20.0 + 2.0 / 3.0))); // entry side
+        assert!(close(b.get_position(), pt2(-89.0, 21.0)));
+        assert!(close(b.get_velocity(), pt2(3.0, 1.0)));
+    }
+
+    #[test]
+    fn absorb() {
+        let mut a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        a.behaviours[Wall::Top.index()] = WallBehaviour::Absorb;
+        let mut b = ball_at(pt2(0.0, 88.0), pt2(3.0, 4.0));
+
+        assert_eq!(a.advance(&mut b)[0].kind, HitKind::Absorb);
+        assert_eq!(b.get_velocity(), pt2(0.0, 0.0));
+        assert!(close(b.get_position(), pt2(1.5, 90.0)));
+        assert!(a.advance(&mut b).is_empty());
+    }
+
+    #[test]
+    fn portal() {
+        let mut a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        a.behaviours[Wall::Right.index()] = WallBehaviour::Portal;
+        a.portals[Wall::Right.index()] = Portal {
+            target: Wall::Top,
+            rotation: 0.0,
+        };
+        let mut b = ball_at(pt2(88.0, 0.0), pt2(3.0, 0.0));
+
+        assert_eq!(a.advance(&mut b)[0].kind, HitKind::Teleport);
+        // enters through the top moving down
+        assert!(close(b.get_position(), pt2(0.0, 89.0)));
+        assert!(close(b.get_velocity(), pt2(0.0, -3.0)));
+
+        // with a rotation of the exit velocity
+        a.portals[Wall::Right.index()].rotation = 90.0;
+        b.set_position(pt2(88.0, 0.0));
+        b.set_velocity(pt2(3.0, 0.0));
+        a.advance(&mut b);
+        assert!(close(b.get_velocity(), pt2(3.0, 0.0)));
+    }
+
+    #[test]
+    fn synced_rotation() {
+        let mut a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        a.rotation = Motion {
+            amplitude: 0.0,
+            rate: 0.25, // one turn every four beats
+            synced: true,
+        };
+        a.update(1.0, 60.0);
+        assert!((a.get_angle() - PI / 2.0).abs() < 1e-5);
+
+        let p = a.to_world(pt2(10.0, 0.0));
+        assert!((p.x - 0.0).abs() < 1e-4);
+        assert!((p.y - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rate_change() {
+        let mut a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        a.rotation = Motion {
+            amplitude: 0.0,
+            rate: 0.25,
+            synced: false,
+        };
+        a.update(1.0, 120.0);
+        assert!((a.get_angle() - PI / 2.0).abs() < 1e-5);
+
+        // the arena turns on from where it is, just faster
+        a.rotation.rate = 0.5;
+        a.update(0.1, 120.0);
+        assert!((a.get_angle() - 0.6 * PI).abs() < 1e-5);
+        assert!((a.angular_velocity - 0.1 * PI).abs() < 1e-5);
+
+        // same for synced wall oscillations & a changed tempo
+        a.wall_motions[Wall::Top.index()] = Motion {
+            amplitude: 10.0,
+            rate: 0.25,
+            synced: true,
+        };
+        a.update(1.0, 60.0); // a quarter cycle
+        assert!((a.get_offset(Wall::Top) - 10.0).abs() < 1e-4);
+        a.update(0.01, 240.0);
+        assert!((a.get_offset(Wall::Top) - 10.0).abs() < 0.05); // a hundredth cycle on
+    }
+}
@@ -0,0 +1,137 @@
+/// A module to model points which attract or repel balls.
+extern crate nannou;
+use nannou::prelude::*;
+use std::collections::HashSet;
+
+// distance at which the strength of an attractor applies unscaled
+const REFERENCE_DISTANCE: f32 = 100.0;
+// closest distance considered, avoids infinite forces
+const MIN_DISTANCE: f32 = 10.0;
+
+/// An attractor pulls balls towards its position,
+/// a repulsor (negative strength) pushes them away.
+pub struct Attractor {
+    pub position: Point2,
+    pub strength: f32, // acceleration (pixels per frame²) at reference distance
+    pub falloff: f32,  // exponent of the distance falloff (2 = inverse square)
+    pub trigger_radius: f32, // distance within which a passing ball triggers a note
+    pub midi_note: u8, // MIDI note to play (128 = none)
+    pub note_root: u8, // root of the key the note was chosen in
+    pub velocity: u8,  // MIDI velocity
+    pub length: u64,   // note length in ms
+    pub midi_channel: u8, // MIDI channel to send data on
+    inside: HashSet<u64>, // balls currently within the trigger radius
+}
+
+impl Attractor {
+    // Create a silent attractor at the given position.
+    pub fn new(position: Point2) -> Attractor {
+        Attractor {
+            position,
+            strength: 0.1,
+            falloff: 2.0,
+            trigger_radius: 30.0,
+            midi_note: 128, // outside MIDI note range
+            note_root: 0,
+            velocity: 64,
+            length: 100,
+            midi_channel: 1,
+            inside: HashSet::new(),
+        }
+    }
+
+    // Acceleration acting on a ball at the given position.
+    pub fn acceleration(&self, p: Point2) -> Point2 {
+        let d = self.position - p;
+        let distance = (d.x * d.x + d.y * d.y).sqrt();
+        if distance == 0.0 {
+            return pt2(0.0, 0.0); // no direction to pull to
+        }
+        let scale = (REFERENCE_DISTANCE / distance.max(MIN_DISTANCE)).powf(self.falloff);
+        d * (self.strength * scale / distance)
+    }
+
+    // Track the position of a ball (identified by id) and
+    // report if it just entered the trigger radius.
+    pub fn passes(&mut self, id: u64, p: Point2) -> bool {
+        let d = self.position - p;
+        if (d.x * d.x + d.y * d.y).sqrt() <= self.trigger_radius {
+            return self.inside.insert(id);
+        }
+        self.inside.remove(&id);
+        false
+    }
+
+    // Stop tracking a ball (e.g. once it is removed).
+    pub fn forget(&mut self, id: u64) {
+        self.inside.remove(&id);
+    }
+
+    // Draw the attractor and its trigger radius.
+    pub fn display(&self, draw: &Draw) {
+        let color = if self.strength >= 0.0 {
+            nannou::color::rgba(0.3, 0.8, 0.4, 0.8) // attract
+        } else {
+            nannou::color::rgba(0.9, 0.3, 0.3, 0.8) // repel
+        };
+        draw.ellipse().xy(self.position).radius(4.0).color(color);
+        if self.midi_note < 128 {
+            draw.ellipse()
+                .xy(self.position)
+                .radius(self.trigger_radius)
+                .no_fill()
+                .stroke(color)
+                .stroke_weight(1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod attractor_test {
+    use super::*;
+
+    #[test]
+    fn attract() {
+        let a = Attractor::new(pt2(0.0, 0.0));
+        let acc = a.acceleration(pt2(100.0, 0.0));
+        assert!((acc.x + 0.1).abs() < 1e-6);
+        assert_eq!(acc.y, 0.0);
+    }
+
+    #[test]
+    fn repel() {
+        let mut a = Attractor::new(pt2(0.0, 0.0));
+        a.strength = -0.1;
+        let acc = a.acceleration(pt2(0.0, 100.0));
+        assert!((acc.y - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn falloff() {
+        let a = Attractor::new(pt2(0.0, 0.0));
+        let near = a.acceleration(pt2(50.0, 0.0));
+        let far = a.acceleration(pt2(200.0, 0.0));
+        assert!((near.x + 0.4).abs() < 1e-6);
+        assert!((far.x + 0.025).abs() < 1e-6);
+
+        // no singularity at the center
+        assert_eq!(a.acceleration(pt2(0.0, 0.0)), pt2(0.0, 0.0));
+        assert!(a.acceleration(pt2(0.001, 0.0)).x > -11.0);
+    }
+
+    #[test]
+    fn passes() {
+        let mut a = Attractor::new(pt2(0.0, 0.0));
+        assert!(!a.passes(0, pt2(50.0, 0.0)));
+        assert!(a.passes(0, pt2(20.0, 0.0)));
+        assert!(!a.passes(0, pt2(10.0, 0.0))); // still inside
+        assert!(!a.passes(0, pt2(40.0, 0.0)));
+        assert!(a.passes(0, pt2(-25.0, 0.0)));
+
+        // balls are tracked individually
+        assert!(a.passes(1, pt2(5.0, 0.0)));
+        assert!(!a.passes(0, pt2(5.0, 0.0)));
+        a.forget(1);
+        assert!(a.passes(1, pt2(5.0, 0.0)));
+    }
+}
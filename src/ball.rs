@@ -6,7 +6,14 @@ use nannou::prelude::*;
 use nannou_osc as osc; // handle Open Sound Control
 
 use crate::circle::Circle;
-use std::io::Result; // use "circle" module of current crate
+use crate::sink::{Event, EventSink, Source};
+use crate::tuning;
+use std::io::{Error, ErrorKind, Result}; // use "circle" module of current crate
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// seconds between the NTP epoch (1900) and the UNIX epoch (1970)
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
 
 /// A (2D) ball is a circle with a velocity vector.
 pub struct Ball {
@@ -161,7 +168,6 @@ impl PdBall {
     // Create a white unit ball with no velocity.
     // The parameter has to contain the target host
     // (where pd is running) and the UDP port.
-    #[allow(dead_code)]
     pub fn new(target: &str) -> PdBall {
         PdBall {
             ball: Ball::new(),
@@ -170,60 +176,17 @@ impl PdBall {
         }
     }
 
-    // Create PdBall with random values, except
-    // for the message target.
+    // Create PdBall with random values (the frequency
+    // of a piano key), except for the message target.
     pub fn random(target: &str) -> PdBall {
         let mut rng = rand::thread_rng();
         PdBall {
             ball: Ball::random(),
-            frequency: rng.gen_range(100.0, 1000.0),
+            frequency: tuning::frequency(rng.gen_range(21, 109) as f32),
             pdsend: fudi_rs::NetSendUdp::new(target),
         }
     }
 
-    // Retrieve current velocity.
-    pub fn get_velocity(&self) -> Point2 {
-        self.ball.get_velocity()
-    }
-
-    // Set the velocity via a vector
-    pub fn set_velocity(&mut self, v: Point2) {
-        self.ball.set_velocity(v);
-    }
-
-    // Set radius of the ball
-    #[allow(dead_code)]
-    pub fn set_radius(&mut self, r: f32) {
-        self.ball.set_radius(r);
-    }
-
-    // Get radius of the ball
-    pub fn get_radius(&self) -> f32 {
-        self.ball.get_radius()
-    }
-
-    // Set position of the ball
-    pub fn set_position(&mut self, p: Point2) {
-        self.ball.set_position(p);
-    }
-
-    // Get position of the ball
-    pub fn get_position(&self) -> Point2 {
-        self.ball.get_position()
-    }
-
-    // Set color of the ball
-    #[allow(dead_code)]
-    pub fn set_color(&mut self, c: Rgba) {
-        self.ball.set_color(c);
-    }
-
-    // Get color of the ball
-    #[allow(dead_code)]
-    pub fn get_color(&self) -> Rgba {
-        self.ball.get_color()
-    }
-
     // Retrieve current frequency associated with the ball.
     #[allow(dead_code)]
     pub fn get_frequency(&self) -> f32 {
@@ -231,7 +194,6 @@ impl PdBall {
     }
 
     // Associate a new frequency with the ball
-    #[allow(dead_code)]
     pub fn set_frequency(&mut self, freq: f32) {
         self.frequency = freq;
     }
@@ -242,9 +204,65 @@ impl PdBall {
         self.pdsend.send(&msg)
     }
 
-    // Draw the ball
-    pub fn display(&self, draw: &Draw) {
-        self.ball.display(draw);
+    // Send a message made of several atoms (e.g. "hit 0 60 64") to pure data.
+    pub fn send_list(&self, atoms: &[String]) -> Result<usize> {
+        let msg = fudi_rs::PdMessage::Generic(atoms.join(" "));
+        self.pdsend.send(&msg)
+    }
+
+    // Send a collision with a wall to pure data:
+    // "hit <wall> <note> <velocity> <x> <y> <frequency>"
+    pub fn send_hit(&self, wall: usize, note: u8, velocity: u8) -> Result<usize> {
+        let p = self.get_position();
+        self.send_list(&[
+            "hit".to_string(),
+            wall.to_string(),
+            note.to_string(),
+            velocity.to_string(),
+            p.x.to_string(),
+            p.y.to_string(),
+            self.frequency.to_string(),
+        ])
+    }
+}
+
+// a PdBall is used like a ball (position, velocity, ...)
+impl Deref for PdBall {
+    type Target = Ball;
+
+    fn deref(&self) -> &Ball {
+        &self.ball
+    }
+}
+
+impl DerefMut for PdBall {
+    fn deref_mut(&mut self) -> &mut Ball {
+        &mut self.ball
+    }
+}
+
+// send events as "hit <wall> ..." or "pass <attractor> ..." messages
+impl EventSink for PdBall {
+    fn name(&self) -> String {
+        "Pd".to_string()
+    }
+
+    fn send(&mut self, event: &Event, _now: Duration) -> Result<()> {
+        self.set_position(event.position);
+        self.set_frequency(event.frequency);
+        let sent = match event.source {
+            Source::Wall(wall, _) => self.send_hit(wall.index(), event.note, event.velocity),
+            Source::Attractor(i) => self.send_list(&[
+                "pass".to_string(),
+                i.to_string(),
+                event.note.to_string(),
+                event.velocity.to_string(),
+                event.position.x.to_string(),
+                event.position.y.to_string(),
+                self.frequency.to_string(),
+            ]),
+        };
+        sent.map(|_| ())
     }
 }
 
@@ -254,10 +272,11 @@ mod pdball_test {
 
     #[test]
     fn random() {
-        let b1 = PdBall::random("127.0.0.2:2345");
-        let b2 = PdBall::random("127.0.0.2:2345");
+        let b = PdBall::random("127.0.0.2:2345");
 
-        assert_ne!(b1.get_frequency(), b2.get_frequency());
+        let pitch = tuning::pitch(b.get_frequency());
+        assert!(pitch > 20.99 && pitch < 108.01);
+        assert!((pitch - pitch.round()).abs() < 0.001); // a key
     }
 
     #[test]
@@ -308,6 +327,26 @@ mod pdball_test {
         b.set_color(c_new);
         assert_eq!(b.get_color(), c_new);
     }
+
+    #[test]
+    fn send_hit() {
+        let receiver = std::net::UdpSocket::bind("127.0.0.1:0").expect("could not bind receiver");
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let target = format!("127.0.0.1:{}", receiver.local_addr().unwrap().port());
+        let mut b = PdBall::new(&target);
+        b.set_position(pt2(1.5, -2.0));
+        b.set_frequency(440.0);
+        assert!(b.send_hit(2, 60, 100).is_ok());
+
+        let mut buffer = [0u8; 256];
+        let n = receiver
+            .recv(&mut buffer)
+            .expect("no FUDI message received");
+        let text = String::from_utf8_lossy(&buffer[..n]);
+        assert_eq!(text.trim_end(), "hit 2 60 100 1.5 -2 440;");
+    }
 }
 
 /// A ball which handles OSC data.
@@ -316,6 +355,7 @@ pub struct OscBall {
     address: String,
     arguments: Vec<osc::Type>,
     sender: Option<osc::Sender<osc::Connected>>,
+    bundled: bool, // send events as timetagged bundles
 }
 
 impl OscBall {
@@ -323,18 +363,21 @@ impl OscBall {
     // The parameter has to contain the target host
     // (where OSC commands can be received) and
     // the port.
-    #[allow(dead_code)]
     pub fn new(target: &str) -> OscBall {
-        let socket = osc::sender()
-            .expect("could not bind to default OSC sender socket")
-            .connect(target)
-            .expect("could not connect to target");
-        OscBall {
+        OscBall::connect(target).expect("could not connect to target")
+    }
+
+    // Create a white unit ball with no velocity sending to the
+    // target (host:port), fails if the target can not be reached.
+    pub fn connect(target: &str) -> Result<OscBall> {
+        let socket = osc::sender()?.connect(target)?;
+        Ok(OscBall {
             ball: Ball::new(),
             address: "".to_string(),
             arguments: vec![],
             sender: Some(socket),
-        }
+            bundled: false,
+        })
     }
 
     // Create OscBall with random core values.
@@ -349,46 +392,6 @@ impl OscBall {
         return oball;
     }
 
-    // Retrieve current velocity.
-    pub fn get_velocity(&self) -> Point2 {
-        self.ball.get_velocity()
-    }
-
-    // Set the velocity via a vector
-    pub fn set_velocity(&mut self, v: Point2) {
-        self.ball.set_velocity(v);
-    }
-
-    // Set radius of the ball
-    pub fn set_radius(&mut self, r: f32) {
-        self.ball.set_radius(r);
-    }
-
-    // Get radius of the ball
-    pub fn get_radius(&self) -> f32 {
-        self.ball.get_radius()
-    }
-
-    // Set position of the ball
-    pub fn set_position(&mut self, p: Point2) {
-        self.ball.set_position(p);
-    }
-
-    // Get position of the ball
-    pub fn get_position(&self) -> Point2 {
-        self.ball.get_position()
-    }
-
-    // Set color of the ball
-    pub fn set_color(&mut self, c: Rgba) {
-        self.ball.set_color(c);
-    }
-
-    // Get color of the ball
-    pub fn get_color(&self) -> Rgba {
-        self.ball.get_color()
-    }
-
     // Set the OSC address.
     pub fn set_address(&mut self, address: String) {
         self.address = address;
@@ -409,28 +412,115 @@ impl OscBall {
         return self.arguments.clone();
     }
 
-    // Send message to pure data.
-    pub fn send(&self) {
-        let osc_args = vec![];
-        let packet = (&self.address, osc_args);
+    // Send events as timetagged bundles (or plain messages).
+    pub fn set_bundled(&mut self, bundled: bool) {
+        self.bundled = bundled;
+    }
+
+    // Check if events are sent as bundles.
+    pub fn is_bundled(&self) -> bool {
+        self.bundled
+    }
+
+    // Send the current address and arguments as OSC message.
+    pub fn send(&self) -> Result<usize> {
+        let packet = (self.address.clone(), self.arguments.clone());
+        self.send_packet(packet.into())
+    }
+
+    // Send messages (address & arguments) as OSC bundle, to be
+    // executed by the receiver at the given time.
+    pub fn send_bundle(
+        &self,
+        messages: Vec<(String, Vec<osc::Type>)>,
+        time: SystemTime,
+    ) -> Result<usize> {
+        let bundle = osc::Bundle {
+            timetag: timetag(time),
+            content: messages
+                .into_iter()
+                .map(|(addr, args)| {
+                    osc::Packet::Message(osc::Message {
+                        addr,
+                        args: Some(args),
+                    })
+                })
+                .collect(),
+        };
+        self.send_packet(osc::Packet::Bundle(bundle))
+    }
+
+    // Send a packet to the target (if any).
+    fn send_packet(&self, packet: osc::Packet) -> Result<usize> {
         match &self.sender {
-            Some(s) => {
-                s.send(packet).ok(); // TODO: handle error more gracefully
-                return;
-            }
-            None => panic!("no OSC target"),
+            Some(s) => s
+                .send(packet)
+                .map_err(|e| Error::new(ErrorKind::Other, format!("{}", e))),
+            None => Err(Error::new(ErrorKind::NotConnected, "no OSC target")),
         }
     }
+}
 
-    // Draw the ball
-    pub fn display(&self, draw: &Draw) {
-        self.ball.display(draw);
+// an OscBall is used like a ball (position, velocity, ...)
+impl Deref for OscBall {
+    type Target = Ball;
+
+    fn deref(&self) -> &Ball {
+        &self.ball
+    }
+}
+
+impl DerefMut for OscBall {
+    fn deref_mut(&mut self) -> &mut Ball {
+        &mut self.ball
+    }
+}
+
+// send events to e.g. "/bq/ball/0/hit/top" with note, velocity, x, y, speed and frequency
+impl EventSink for OscBall {
+    fn name(&self) -> String {
+        "OSC".to_string()
+    }
+
+    fn send(&mut self, event: &Event, now: Duration) -> Result<()> {
+        let address = match event.source {
+            Source::Wall(wall, _) => format!("/bq/ball/{}/hit/{}", event.ball, wall.name()),
+            Source::Attractor(i) => format!("/bq/ball/{}/pass/{}", event.ball, i),
+        };
+        let args = vec![
+            osc::Type::Int(event.note as i32),
+            osc::Type::Int(event.velocity as i32),
+            osc::Type::Float(event.position.x),
+            osc::Type::Float(event.position.y),
+            osc::Type::Float(event.speed),
+            osc::Type::Float(event.frequency),
+        ];
+        self.set_position(event.position);
+        let sent = if self.bundled {
+            let delay = event.time.checked_sub(now).unwrap_or_default();
+            self.send_bundle(vec![(address, args)], SystemTime::now() + delay)
+        } else {
+            self.set_address(address);
+            self.set_arguments(args);
+            OscBall::send(self)
+        };
+        sent.map(|_| ())
     }
 }
 
+/// Convert a point in time into an OSC (NTP) timetag: seconds since 1900
+/// and the fraction of a second (in 1/2^32 s).
+pub fn timetag(time: SystemTime) -> (u32, u32) {
+    let since_unix = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_unix.as_secs() + NTP_UNIX_OFFSET;
+    let fraction = ((since_unix.subsec_nanos() as u64) << 32) / 1_000_000_000;
+    (seconds as u32, fraction as u32)
+}
+
 #[cfg(test)]
 mod oscball_test {
     use super::*;
+    use std::time::Duration;
 
     #[test]
     fn random() {
@@ -496,4 +586,40 @@ mod oscball_test {
         b.set_arguments(t_args.clone());
         assert_eq!(b.get_arguments(), t_args);
     }
+
+    #[test]
+    fn send() {
+        let receiver = osc::receiver(0).expect("could not bind OSC receiver");
+        let target = format!("127.0.0.1:{}", receiver.local_addr().unwrap().port());
+        let mut b = OscBall::new(&target);
+        b.set_address("/bq/ball/0/hit/top".to_string());
+        b.set_arguments(vec![osc::Type::Int(60), osc::Type::Float(0.5)]);
+        assert!(b.send().is_ok());
+
+        let (packet, _) = receiver.recv().expect("no OSC packet received");
+        match packet {
+            osc::Packet::Message(msg) => {
+                assert_eq!(msg.addr, "/bq/ball/0/hit/top");
+                assert_eq!(
+                    msg.args,
+                    Some(vec![osc::Type::Int(60), osc::Type::Float(0.5)])
+                );
+            }
+            osc::Packet::Bundle(_) => panic!("expected a message"),
+        }
+    }
+
+    #[test]
+    fn invalid_target() {
+        assert!(OscBall::connect("127.0.0.1:2345").is_ok());
+        assert!(OscBall::connect("no port").is_err());
+        assert!(OscBall::connect("127.0.0.1:99999").is_err());
+    }
+
+    #[test]
+    fn ntp_timetag() {
+        assert_eq!(timetag(UNIX_EPOCH), (2_208_988_800, 0));
+        let half = UNIX_EPOCH + Duration::from_millis(1500);
+        assert_eq!(timetag(half), (2_208_988_801, 1 << 31));
+    }
 }
@@ -0,0 +1,234 @@
+/// A module to describe changes requested by remote controllers (OSC, pd, ...).
+use crate::arena::Wall;
+use crate::zone::{Zones, MAX_ZONES};
+
+/// A single value of a remote message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Atom {
+    Float(f32),
+    Symbol(String),
+}
+
+impl Atom {
+    // The atom as number (symbols like "3" count as numbers as well).
+    pub fn number(&self) -> Option<f32> {
+        match self {
+            Atom::Float(f) => Some(*f),
+            Atom::Symbol(s) => s.parse::<f32>().ok(),
+        }
+    }
+
+    // The atom as text.
+    pub fn text(&self) -> String {
+        match self {
+            Atom::Float(f) => f.to_string(),
+            Atom::Symbol(s) => s.clone(),
+        }
+    }
+}
+
+/// A change of the model requested by a remote controller.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    BallVelocity(u64, f32, f32),  // ball id (0 = main ball), velocity
+    WallNote(Wall, u8),           // note to play (128 = none)
+    WallVelocity(Wall, u8),       // MIDI velocity
+    WallLength(Wall, u64),        // note length in ms
+    WallChannel(Wall, u8),        // MIDI channel
+    WallProgram(Wall, u8),        // program change with each note (128 = none)
+    WallController(Wall, u8, u8), // controller number & value sent with each note
+    WallZones(Wall, Zones),       // pitch depending on where the wall is hit
+    Tempo(f32),                   // bpm
+    Reference(f32),               // frequency of the reference note (A4) in Hz
+    ArenaSize(f32, f32),          // width, height
+    Play,                         // start the transport
+    Stop,                         // stop the transport
+    LoadProject(String),          // project name
+    SaveProject(String),          // project name
+}
+
+impl Command {
+    // Parse a command from its atoms, e.g. "wall top note 60" or
+    // "ball 0 velocity 2.5 -1" (OSC addresses are split at the slashes).
+    pub fn parse(atoms: &[Atom]) -> Result<Command, String> {
+        let words: Vec<String> = atoms.iter().map(|a| a.text()).collect();
+        let words: Vec<&str> = words.iter().map(|w| w.as_str()).collect();
+        let number = |i: usize| -> Result<f32, String> {
+            match atoms.get(i).and_then(|a| a.number()) {
+                Some(n) if n.is_finite() => Ok(n),
+                Some(n) => Err(format!("invalid number {} at position {}", n, i + 1)),
+                None => Err(format!("expected a number at position {}", i + 1)),
+            }
+        };
+        let midi_value = |i: usize, max: f32| -> Result<u8, String> {
+            let n = number(i)?;
+            if n < 0.0 || n > max {
+                return Err(format!("{} out of range (0 - {})", n, max));
+            }
+            Ok(n as u8)
+        };
+
+        match words.as_slice() {
+            ["ball", _, "velocity", _, _] => {
+                let id = number(1)?;
+                if id < 0.0 {
+                    return Err(format!("invalid ball id {}", id));
+                }
+                Ok(Command::BallVelocity(id as u64, number(3)?, number(4)?))
+            }
+            ["wall", name, "cc", _, _] => match Wall::from_name(name) {
+                Some(wall) => Ok(Command::WallController(
+                    wall,
+                    midi_value(3, 127.0)?,
+                    midi_value(4, 127.0)?,
+                )),
+                None => Err(format!("unknown wall {}", name)),
+            },
+            ["wall", name, what, _] => {
+                let wall = match Wall::from_name(name) {
+                    Some(wall) => wall,
+                    None => return Err(format!("unknown wall {}", name)),
+                };
+                match *what {
+                    "note" => Ok(Command::WallNote(wall, midi_value(3, 128.0)?)),
+                    "velocity" => Ok(Command::WallVelocity(wall, midi_value(3, 127.0)?)),
+                    "program" => Ok(Command::WallProgram(wall, midi_value(3, 128.0)?)),
+                    "zones" => Ok(Command::WallZones(
+                        wall,
+                        Zones::new(1, midi_value(3, MAX_ZONES as f32)?),
+                    )),
+                    "glide" => Ok(Command::WallZones(
+                        wall,
+                        Zones::new(2, midi_value(3, MAX_ZONES as f32)?),
+                    )),
+                    "length" => Ok(Command::WallLength(wall, number(3)?.max(10.0) as u64)),
+                    "channel" => match midi_value(3, 16.0)? {
+                        0 => Err("MIDI channels start at 1".to_string()),
+                        channel => Ok(Command::WallChannel(wall, channel)),
+                    },
+                    _ => Err(format!("unknown wall setting {}", what)),
+                }
+            }
+            ["tempo", _] => Ok(Command::Tempo(number(1)?)),
+            ["tuning", "reference", _] => match number(2)? {
+                f if f > 0.0 => Ok(Command::Reference(f)),
+                f => Err(format!("invalid reference frequency {}", f)),
+            },
+            ["arena", "size", _, _] => Ok(Command::ArenaSize(number(2)?, number(3)?)),
+            ["transport", "play"] => Ok(Command::Play),
+            ["transport", "stop"] => Ok(Command::Stop),
+            ["project", "load", name] => Ok(Command::LoadProject(project_name(name)?)),
+            ["project", "save", name] => Ok(Command::SaveProject(project_name(name)?)),
+            _ => Err(format!("unknown command: {}", words.join(" "))),
+        }
+    }
+}
+
+/// Check that a project name refers to a file in the working directory only.
+fn project_name(name: &str) -> Result<String, String> {
+    if name.is_empty()
+        || name.contains('/')
+        || name.contains('\\')
+        || name.contains("..")
+        || name.chars().any(|c| c.is_control())
+        || std::path::Path::new(name).is_absolute()
+    {
+        return Err(format!("invalid project name {:?}", name));
+    }
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod command_test {
+    use super::*;
+
+    fn atoms(text: &str) -> Vec<Atom> {
+        text.split_whitespace()
+            .map(|w| Atom::Symbol(w.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Command::parse(&atoms("ball 0 velocity 2.5 -1")),
+            Ok(Command::BallVelocity(0, 2.5, -1.0))
+        );
+        assert_eq!(
+            Command::parse(&atoms("wall top note 60")),
+            Ok(Command::WallNote(Wall::Top, 60))
+        );
+        assert_eq!(
+            Command::parse(&atoms("wall left channel 10")),
+            Ok(Command::WallChannel(Wall::Left, 10))
+        );
+        assert_eq!(
+            Command::parse(&atoms("wall bottom program 5")),
+            Ok(Command::WallProgram(Wall::Bottom, 5))
+        );
+        assert_eq!(
+            Command::parse(&atoms("wall top cc 74 100")),
+            Ok(Command::WallController(Wall::Top, 74, 100))
+        );
+        assert_eq!(
+            Command::parse(&atoms("wall right zones 8")),
+            Ok(Command::WallZones(Wall::Right, Zones::Steps(8)))
+        );
+        assert_eq!(
+            Command::parse(&atoms("wall left glide 0")),
+            Ok(Command::WallZones(Wall::Left, Zones::Off))
+        );
+        assert_eq!(Command::parse(&atoms("tempo 90")), Ok(Command::Tempo(90.0)));
+        assert_eq!(
+            Command::parse(&atoms("tuning reference 432")),
+            Ok(Command::Reference(432.0))
+        );
+        assert!(Command::parse(&atoms("tuning reference 0")).is_err());
+        assert_eq!(
+            Command::parse(&atoms("arena size 300 200")),
+            Ok(Command::ArenaSize(300.0, 200.0))
+        );
+        assert_eq!(Command::parse(&atoms("transport play")), Ok(Command::Play));
+        assert_eq!(
+            Command::parse(&atoms("project load groove")),
+            Ok(Command::LoadProject("groove".to_string()))
+        );
+    }
+
+    #[test]
+    fn typed_atoms() {
+        let a = vec![
+            Atom::Symbol("wall".to_string()),
+            Atom::Symbol("right".to_string()),
+            Atom::Symbol("velocity".to_string()),
+            Atom::Float(100.0),
+        ];
+        assert_eq!(
+            Command::parse(&a),
+            Ok(Command::WallVelocity(Wall::Right, 100))
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Command::parse(&atoms("wall middle note 60")).is_err());
+        assert!(Command::parse(&atoms("wall top note 200")).is_err());
+        assert!(Command::parse(&atoms("wall top channel 0")).is_err());
+        assert!(Command::parse(&atoms("wall top cc 74 128")).is_err());
+        assert!(Command::parse(&atoms("ball 0 velocity fast 1")).is_err());
+        assert!(Command::parse(&atoms("ball 0 velocity NaN 1")).is_err());
+        assert!(Command::parse(&atoms("ball 0 velocity 1 inf")).is_err());
+        assert!(Command::parse(&atoms("ball 0 velocity -inf 1")).is_err());
+        assert!(Command::parse(&atoms("project save ../groove")).is_err());
+        assert!(Command::parse(&atoms("project save /tmp/groove")).is_err());
+        assert!(Command::parse(&atoms("project load sub\\groove")).is_err());
+        assert!(Command::parse(&[
+            Atom::Symbol("project".to_string()),
+            Atom::Symbol("save".to_string()),
+            Atom::Symbol("a\u{0}b".to_string()),
+        ])
+        .is_err());
+        assert!(Command::parse(&atoms("transport rewind")).is_err());
+        assert!(Command::parse(&[]).is_err());
+    }
+}
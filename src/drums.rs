@@ -0,0 +1,203 @@
+/// A module to name the notes of drum machines (General MIDI or own maps).
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// MIDI channel of the drums in General MIDI
+pub const CHANNEL: u8 = 10;
+// file extension of drum maps
+pub const EXTENSION: &str = "drums";
+
+/// The General MIDI percussion key map.
+pub const GM_DRUMS: [(&str, u8); 47] = [
+    ("Acoustic Bass Drum", 35),
+    ("Bass Drum 1", 36),
+    ("Side Stick", 37),
+    ("Acoustic Snare", 38),
+    ("Hand Clap", 39),
+    ("Electric Snare", 40),
+    ("Low Floor Tom", 41),
+    ("Closed Hi-Hat", 42),
+    ("High Floor Tom", 43),
+    ("Pedal Hi-Hat", 44),
+    ("Low Tom", 45),
+    ("Open Hi-Hat", 46),
+    ("Low-Mid Tom", 47),
+    ("Hi-Mid Tom", 48),
+    ("Crash Cymbal 1", 49),
+    ("High Tom", 50),
+    ("Ride Cymbal 1", 51),
+    ("Chinese Cymbal", 52),
+    ("Ride Bell", 53),
+    ("Tambourine", 54),
+    ("Splash Cymbal", 55),
+    ("Cowbell", 56),
+    ("Crash Cymbal 2", 57),
+    ("Vibraslap", 58),
+    ("Ride Cymbal 2", 59),
+    ("Hi Bongo", 60),
+    ("Low Bongo", 61),
+    ("Mute Hi Conga", 62),
+    ("Open Hi Conga", 63),
+    ("Low Conga", 64),
+    ("High Timbale", 65),
+    ("Low Timbale", 66),
+    ("High Agogo", 67),
+    ("Low Agogo", 68),
+    ("Cabasa", 69),
+    ("Maracas", 70),
+    ("Short Whistle", 71),
+    ("Long Whistle", 72),
+    ("Short Guiro", 73),
+    ("Long Guiro", 74),
+    ("Claves", 75),
+    ("Hi Wood Block", 76),
+    ("Low Wood Block", 77),
+    ("Mute Cuica", 78),
+    ("Open Cuica", 79),
+    ("Mute Triangle", 80),
+    ("Open Triangle", 81),
+];
+
+/// A drum sound and the note triggering it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Drum {
+    pub name: String,
+    pub note: u8,
+}
+
+/// The drums of a drum machine, e.g. read from a file "tr-8.drums" like
+/// {"name": "TR-8", "drums": [{"name": "kick", "note": 36}, ...]}.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DrumMap {
+    pub name: String,
+    pub drums: Vec<Drum>, // in the order they are listed
+}
+
+impl DrumMap {
+    // The General MIDI drums.
+    pub fn general_midi() -> DrumMap {
+        DrumMap {
+            name: "General MIDI".to_string(),
+            drums: GM_DRUMS
+                .iter()
+                .map(|(name, note)| Drum {
+                    name: name.to_string(),
+                    note: *note,
+                })
+                .collect(),
+        }
+    }
+
+    // Read a drum map from JSON text.
+    pub fn parse(text: &str) -> Result<DrumMap, String> {
+        let map: DrumMap = serde_json::from_str(text).map_err(|e| format!("{}", e))?;
+        if map.drums.is_empty() {
+            return Err("no drums".to_string());
+        }
+        if let Some(drum) = map.drums.iter().find(|d| d.note > 127) {
+            return Err(format!("note {} of {} out of range", drum.note, drum.name));
+        }
+        Ok(map)
+    }
+
+    // Read a drum map from a file.
+    pub fn load(path: &Path) -> Result<DrumMap, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}", e))?;
+        DrumMap::parse(&text)
+    }
+
+    // Name of the drum a note triggers (if any).
+    pub fn name_of(&self, note: u8) -> Option<&str> {
+        self.drums
+            .iter()
+            .find(|d| d.note == note)
+            .map(|d| d.name.as_str())
+    }
+
+    // The drum listed the given number of places after the one of a note
+    // (as far as the list goes), notes not in the map stay as they are.
+    pub fn step_up(&self, note: u8, steps: usize) -> u8 {
+        match self.drums.iter().position(|d| d.note == note) {
+            Some(i) => self.drums[(i + steps).min(self.drums.len() - 1)].note,
+            None => note,
+        }
+    }
+}
+
+/// General MIDI followed by the drum maps (files ending in ".drums") found
+/// in a directory, maps that can not be read are reported.
+pub fn load_all(dir: &Path) -> Vec<DrumMap> {
+    let mut maps = vec![DrumMap::general_midi()];
+    let mut paths: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().map_or(false, |ext| ext == EXTENSION))
+            .collect(),
+        Err(e) => {
+            eprintln!("drum map error: {}: {}", dir.display(), e);
+            return maps;
+        }
+    };
+    paths.sort();
+    for path in paths {
+        match DrumMap::load(&path) {
+            Ok(map) => maps.push(map),
+            Err(e) => eprintln!("drum map error: {}: {}", path.display(), e),
+        }
+    }
+    maps
+}
+
+#[cfg(test)]
+mod drums_test {
+    use super::*;
+
+    const TR8: &str = r#"{"name": "TR-8", "drums": [
+        {"name": "kick", "note": 36},
+        {"name": "snare", "note": 38},
+        {"name": "closed hat", "note": 42}
+    ]}"#;
+
+    #[test]
+    fn general_midi() {
+        let gm = DrumMap::general_midi();
+        assert_eq!(gm.name_of(36), Some("Bass Drum 1"));
+        assert_eq!(gm.name_of(42), Some("Closed Hi-Hat"));
+        assert_eq!(gm.name_of(20), None);
+        assert_eq!(gm.drums.last().unwrap().note, 81);
+    }
+
+    #[test]
+    fn parse() {
+        let map = DrumMap::parse(TR8).unwrap();
+        assert_eq!(map.name, "TR-8");
+        assert_eq!(map.name_of(38), Some("snare"));
+        assert!(DrumMap::parse(r#"{"name": "empty", "drums": []}"#).is_err());
+        assert!(DrumMap::parse(r#"{"name": "x", "drums": [{"name": "a", "note": 200}]}"#).is_err());
+        assert!(DrumMap::parse("kick 36").is_err());
+    }
+
+    #[test]
+    fn steps() {
+        let map = DrumMap::parse(TR8).unwrap();
+        assert_eq!(map.step_up(36, 0), 36);
+        assert_eq!(map.step_up(36, 2), 42);
+        assert_eq!(map.step_up(38, 5), 42); // the last drum
+        assert_eq!(map.step_up(60, 1), 60); // not a drum of the map
+    }
+
+    #[test]
+    fn directory() {
+        let dir = std::env::temp_dir().join(format!("bouncyquencer-drums-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("tr-8.drums"), TR8).unwrap();
+        fs::write(dir.join("broken.drums"), "{").unwrap();
+        fs::write(dir.join("notes.txt"), TR8).unwrap();
+
+        let maps = load_all(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        let names: Vec<&str> = maps.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["General MIDI", "TR-8"]);
+    }
+}
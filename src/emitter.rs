@@ -0,0 +1,185 @@
+/// A module to model emitters which launch balls on a rhythm.
+extern crate nannou;
+extern crate rand;
+use nannou::prelude::*;
+use rand::Rng;
+
+use crate::ball::Ball;
+
+/// An emitter launches new balls at a set rate (or on demand).
+pub struct Emitter {
+    pub position: Point2,
+    pub rate: f32,         // balls per beat (0 = only on demand)
+    pub direction: f32,    // launch direction in degrees
+    pub spread: f32,       // random deviation from the direction in degrees
+    pub speed: f32,        // launch speed (pixels per frame)
+    pub speed_spread: f32, // random deviation from the speed (fraction)
+    pub lifetime: f32,     // seconds until a ball is removed (0 = forever)
+    pub max_hits: u32,     // hits until a ball is removed (0 = unlimited)
+    pub on_midi: bool,     // launch on incoming MIDI notes
+    pub color: Rgba,       // color of the launched balls
+    phase: f32,            // progress towards the next launch
+}
+
+impl Emitter {
+    // Create an emitter launching one ball per beat.
+    pub fn new(position: Point2) -> Emitter {
+        let mut rng = rand::thread_rng();
+        let (r, g, b) = rng.gen::<(f32, f32, f32)>();
+        Emitter {
+            position,
+            rate: 1.0,
+            direction: 90.0,
+            spread: 30.0,
+            speed: 5.0,
+            speed_spread: 0.0,
+            lifetime: 8.0,
+            max_hits: 0,
+            on_midi: false,
+            color: Rgba::new(r, g, b, 1.0),
+            phase: 0.0,
+        }
+    }
+
+    // Advance time by dt seconds and return how many balls are due.
+    pub fn update(&mut self, dt: f32, bpm: f32) -> usize {
+        self.phase += self.rate * dt * bpm / 60.0;
+        let due = self.phase.floor();
+        self.phase -= due;
+        due as usize
+    }
+
+    // Create a new ball at the position of the emitter.
+    pub fn launch(&self) -> Ball {
+        let mut rng = rand::thread_rng();
+        let mut direction = self.direction;
+        if self.spread > 0.0 {
+            direction += rng.gen_range(-self.spread, self.spread);
+        }
+        let mut speed = self.speed;
+        if self.speed_spread > 0.0 {
+            speed *= 1.0 + rng.gen_range(-self.speed_spread, self.speed_spread);
+        }
+        let a = direction.to_radians();
+
+        let mut ball = Ball::new();
+        ball.set_radius(8.0);
+        ball.set_color(self.color);
+        ball.set_position(self.position);
+        ball.set_velocity(pt2(a.cos(), a.sin()) * speed);
+        ball
+    }
+
+    // Draw the emitter and its launch direction.
+    pub fn display(&self, draw: &Draw) {
+        let a = self.direction.to_radians();
+        draw.ellipse()
+            .xy(self.position)
+            .radius(5.0)
+            .color(self.color);
+        draw.line()
+            .start(self.position)
+            .end(self.position + pt2(a.cos(), a.sin()) * 15.0)
+            .weight(2.0)
+            .color(self.color);
+    }
+}
+
+/// A ball launched by an emitter, removed after its lifetime or hit count.
+pub struct EmittedBall {
+    pub id: u64, // identifies the ball (e.g. for attractors)
+    pub ball: Ball,
+    pub age: f32,       // seconds since launch
+    pub hits: u32,      // walls hit since launch
+    pub absorbed: bool, // got stuck in a wall
+    lifetime: f32,
+    max_hits: u32,
+}
+
+impl EmittedBall {
+    // Launch a ball from the given emitter.
+    pub fn new(id: u64, emitter: &Emitter) -> EmittedBall {
+        EmittedBall {
+            id,
+            ball: emitter.launch(),
+            age: 0.0,
+            hits: 0,
+            absorbed: false,
+            lifetime: emitter.lifetime,
+            max_hits: emitter.max_hits,
+        }
+    }
+
+    // Check if the ball should be removed.
+    pub fn expired(&self) -> bool {
+        (self.lifetime > 0.0 && self.age >= self.lifetime)
+            || (self.max_hits > 0 && self.hits >= self.max_hits)
+            || self.absorbed
+    }
+}
+
+#[cfg(test)]
+mod emitter_test {
+    use super::*;
+
+    #[test]
+    fn rate() {
+        let mut e = Emitter::new(pt2(0.0, 0.0));
+        e.rate = 2.0;
+        // 120 bpm -> 4 balls per second
+        assert_eq!(e.update(0.1, 120.0), 0);
+        assert_eq!(e.update(0.2, 120.0), 1);
+        assert_eq!(e.update(1.0, 120.0), 4);
+    }
+
+    #[test]
+    fn on_demand_only() {
+        let mut e = Emitter::new(pt2(0.0, 0.0));
+        e.rate = 0.0;
+        assert_eq!(e.update(10.0, 120.0), 0);
+    }
+
+    #[test]
+    fn launch() {
+        let mut e = Emitter::new(pt2(10.0, 20.0));
+        e.direction = 0.0;
+        e.spread = 0.0;
+        e.speed = 3.0;
+        let b = e.launch();
+        assert_eq!(b.get_position(), pt2(10.0, 20.0));
+        assert!((b.get_velocity().x - 3.0).abs() < 1e-6);
+        assert!(b.get_velocity().y.abs() < 1e-6);
+
+        e.spread = 45.0;
+        for _ in 0..10 {
+            let v = e.launch().get_velocity();
+            assert!(v.x > 0.0);
+            assert!(v.y.abs() <= v.x + 1e-6);
+        }
+    }
+
+    #[test]
+    fn expire() {
+        let mut e = Emitter::new(pt2(0.0, 0.0));
+        e.lifetime = 2.0;
+        e.max_hits = 3;
+        let mut b = EmittedBall::new(1, &e);
+        assert!(!b.expired());
+
+        b.hits = 3;
+        assert!(b.expired());
+
+        b.hits = 0;
+        b.age = 2.5;
+        assert!(b.expired());
+
+        e.lifetime = 0.0;
+        e.max_hits = 0;
+        let mut b = EmittedBall::new(2, &e);
+        b.age = 100.0;
+        b.hits = 100;
+        assert!(!b.expired());
+        b.absorbed = true;
+        assert!(b.expired());
+    }
+}
@@ -0,0 +1,280 @@
+/// A module to receive FUDI messages, the protocol of pure data's [netsend].
+use std::io::{ErrorKind, Read, Result};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+
+use crate::command::{Atom, Command};
+
+// largest chunk of data read at once
+const BUFFER_SIZE: usize = 65536;
+// most TCP senders connected at the same time
+const MAX_CLIENTS: usize = 8;
+// most text of an unfinished message kept per TCP sender
+const MAX_PENDING: usize = 4096;
+
+/// Split FUDI text into messages: atoms separated by whitespace, terminated
+/// by ";" (a backslash escapes the next character). Text of an incomplete
+/// message stays in the buffer until the rest arrives.
+pub fn parse(buffer: &mut String) -> Vec<Vec<Atom>> {
+    let mut messages = Vec::new();
+    let mut atoms = Vec::new();
+    let mut word = String::new();
+    let mut escaped = false;
+    let mut consumed = 0; // bytes of complete messages
+
+    for (i, c) in buffer.char_indices() {
+        if escaped {
+            word.push(c);
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            ';' => {
+                push_atom(&mut word, &mut atoms);
+                if !atoms.is_empty() {
+                    messages.push(std::mem::replace(&mut atoms, Vec::new()));
+                }
+                consumed = i + 1;
+            }
+            c if c.is_whitespace() => push_atom(&mut word, &mut atoms),
+            c => word.push(c),
+        }
+    }
+    buffer.drain(..consumed);
+    messages
+}
+
+// Finish a word and add it as atom (numbers become floats).
+fn push_atom(word: &mut String, atoms: &mut Vec<Atom>) {
+    if word.is_empty() {
+        return;
+    }
+    let text = std::mem::replace(word, String::new());
+    atoms.push(match text.parse::<f32>() {
+        Ok(f) => Atom::Float(f),
+        Err(_) => Atom::Symbol(text),
+    });
+}
+
+/// Listens on a port for FUDI messages like "transport play;" or
+/// "wall top note 60;", sent via UDP ([netsend -u]) or TCP ([netsend]).
+/// A TCP sender is dropped when its unfinished message gets too long.
+pub struct FudiReceiver {
+    udp: UdpSocket,
+    tcp: TcpListener,
+    clients: Vec<(TcpStream, String)>, // connected senders & their unparsed text
+    port: u16,
+}
+
+impl FudiReceiver {
+    // Listen on the given host address (e.g. "127.0.0.1" for this computer
+    // only) and port (0 = any free port) for UDP and TCP.
+    pub fn new(host: &str, port: u16) -> Result<FudiReceiver> {
+        let udp = UdpSocket::bind((host, port))?;
+        let port = udp.local_addr()?.port();
+        let tcp = TcpListener::bind((host, port))?;
+        udp.set_nonblocking(true)?;
+        tcp.set_nonblocking(true)?;
+        Ok(FudiReceiver {
+            udp,
+            tcp,
+            clients: vec![],
+            port,
+        })
+    }
+
+    // The port listened on.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    // Collect the commands received since the last poll (without blocking).
+    pub fn poll(&mut self) -> Vec<std::result::Result<Command, String>> {
+        let mut messages = Vec::<Vec<Atom>>::new();
+        let mut errors = Vec::<String>::new();
+        let mut buffer = [0u8; BUFFER_SIZE];
+
+        // every datagram holds complete messages
+        loop {
+            match self.udp.recv_from(&mut buffer) {
+                Ok((n, _)) => {
+                    let mut text = String::from_utf8_lossy(&buffer[..n]).to_string();
+                    messages.extend(parse(&mut text));
+                }
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    errors.push(format!("{}", e));
+                    break;
+                }
+            }
+        }
+
+        // new senders connecting via TCP
+        loop {
+            match self.tcp.accept() {
+                Ok((_, address)) if self.clients.len() >= MAX_CLIENTS => {
+                    errors.push(format!("too many senders, refused {}", address));
+                }
+                Ok((stream, _)) => match stream.set_nonblocking(true) {
+                    Ok(_) => self.clients.push((stream, String::new())),
+                    Err(e) => errors.push(format!("{}", e)),
+                },
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    errors.push(format!("{}", e));
+                    break;
+                }
+            }
+        }
+
+        // a TCP stream may split messages at any point
+        let mut i = 0;
+        while i < self.clients.len() {
+            let (stream, text) = &mut self.clients[i];
+            match stream.read(&mut buffer) {
+                Ok(0) => {
+                    self.clients.remove(i); // disconnected
+                }
+                Ok(n) => {
+                    text.push_str(&String::from_utf8_lossy(&buffer[..n]));
+                    messages.extend(parse(text));
+                    if text.len() > MAX_PENDING {
+                        errors.push("message too long, sender dropped".to_string());
+                        self.clients.remove(i);
+                    }
+                }
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => i += 1,
+                Err(e) => {
+                    errors.push(format!("{}", e));
+                    self.clients.remove(i);
+                }
+            }
+        }
+
+        let mut commands: Vec<std::result::Result<Command, String>> =
+            errors.into_iter().map(Err).collect();
+        commands.extend(messages.iter().map(|atoms| Command::parse(atoms)));
+        commands
+    }
+}
+
+#[cfg(test)]
+mod fudi_test {
+    use super::*;
+    use crate::arena::Wall;
+    use std::io::Write;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    fn poll_until(
+        receiver: &mut FudiReceiver,
+        count: usize,
+    ) -> Vec<std::result::Result<Command, String>> {
+        let mut commands = vec![];
+        for _ in 0..100 {
+            commands.extend(receiver.poll());
+            if commands.len() >= count {
+                break;
+            }
+            sleep(Duration::from_millis(10));
+        }
+        commands
+    }
+
+    #[test]
+    fn parse_messages() {
+        let mut text = "transport play;\nwall top note 60;\nball 0 velocity 2.5 -1;".to_string();
+        let messages = parse(&mut text);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[1],
+            vec![
+                Atom::Symbol("wall".to_string()),
+                Atom::Symbol("top".to_string()),
+                Atom::Symbol("note".to_string()),
+                Atom::Float(60.0),
+            ]
+        );
+        assert_eq!(messages[2][4], Atom::Float(-1.0));
+        assert!(text.is_empty());
+    }
+
+    #[test]
+    fn parse_partial() {
+        let mut text = "transport play; arena si".to_string();
+        assert_eq!(parse(&mut text).len(), 1);
+        assert_eq!(text, " arena si");
+
+        text.push_str("ze 300 200;");
+        let messages = parse(&mut text);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0][2], Atom::Float(300.0));
+    }
+
+    #[test]
+    fn parse_escaped() {
+        let mut text = "project load my\\ song\\;2;;".to_string();
+        let messages = parse(&mut text);
+        assert_eq!(messages.len(), 1); // empty messages are skipped
+        assert_eq!(messages[0][2], Atom::Symbol("my song;2".to_string()));
+    }
+
+    #[test]
+    fn receive_udp() {
+        let mut receiver = FudiReceiver::new("127.0.0.1", 0).expect("could not listen");
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .send_to(b"transport stop;\n", ("127.0.0.1", receiver.port()))
+            .unwrap();
+
+        assert_eq!(poll_until(&mut receiver, 1), vec![Ok(Command::Stop)]);
+    }
+
+    #[test]
+    fn receive_tcp() {
+        let mut receiver = FudiReceiver::new("127.0.0.1", 0).expect("could not listen");
+        let mut stream = TcpStream::connect(("127.0.0.1", receiver.port())).unwrap();
+        stream.write_all(b"wall left note 62;\narena size").unwrap();
+        stream.flush().unwrap();
+        sleep(Duration::from_millis(20));
+        stream.write_all(b" 300 200;\n").unwrap();
+
+        assert_eq!(
+            poll_until(&mut receiver, 2),
+            vec![
+                Ok(Command::WallNote(Wall::Left, 62)),
+                Ok(Command::ArenaSize(300.0, 200.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn too_many_senders() {
+        let mut receiver = FudiReceiver::new("127.0.0.1", 0).expect("could not listen");
+        let streams: Vec<TcpStream> = (0..MAX_CLIENTS + 1)
+            .map(|_| TcpStream::connect(("127.0.0.1", receiver.port())).unwrap())
+            .collect();
+
+        let errors = poll_until(&mut receiver, 1);
+        assert!(errors[0]
+            .as_ref()
+            .unwrap_err()
+            .starts_with("too many senders"));
+        assert_eq!(receiver.clients.len(), MAX_CLIENTS);
+        drop(streams);
+    }
+
+    #[test]
+    fn too_long() {
+        let mut receiver = FudiReceiver::new("127.0.0.1", 0).expect("could not listen");
+        let mut stream = TcpStream::connect(("127.0.0.1", receiver.port())).unwrap();
+        stream.write_all(&[b'a'; MAX_PENDING + 1]).unwrap();
+
+        let errors = poll_until(&mut receiver, 1);
+        assert_eq!(
+            errors,
+            vec![Err("message too long, sender dropped".to_string())]
+        );
+        assert!(receiver.clients.is_empty());
+    }
+}
@@ -8,7 +8,7 @@ use nannou::ui::prelude::*;
 use nannou::ui::Color::Rgba;
 
 extern crate midir; // handle MIDI interfaces
-use midir::MidiOutput;
+use midir::{MidiOutput, MidiOutputConnection};
 
 extern crate wmidi; // data-structures to handle MIDI messages
 use wmidi::MidiMessage;
@@ -29,19 +29,66 @@ use circle::Circle;
 
 mod mididata;
 
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::fs;
 use std::io::{stdin, stdout, Write};
+use std::net::ToSocketAddrs;
 use std::path::Path;
+use std::sync::mpsc;
 use std::thread::sleep;
-use std::time::Duration;
-
-use core::cmp::Ordering;
+use std::time::{Duration, SystemTime};
 
 // use content of src/ball.rs
 mod ball;
-use ball::Ball;
+use ball::{Ball, OscBall, PdBall};
+
+mod arena;
+use arena::{Arena, Hit, HitKind, Wall, WallBehaviour};
+
+mod physics;
+use physics::Physics;
+
+mod attractor;
+use attractor::Attractor;
+
+mod emitter;
+use emitter::{EmittedBall, Emitter};
+
+mod rhythm;
+
+mod command;
+use command::Command;
+
+mod remote;
+use remote::OscRemote;
+
+mod fudi;
+use fudi::FudiReceiver;
+
+mod stream;
+use stream::{BallState, HitState, NoteState, Report, StateServer};
+
+mod midi;
+use midi::{
+    ActiveNotes, Bends, Expression, MidiQueue, Mpe, Retune, TimedMidiMessage, TimedMidiMessageType,
+    TuningOutput, Tunings,
+};
+
+mod drums;
+use drums::DrumMap;
+
+mod scale;
+use scale::{Key, Scale};
+
+mod tuning;
+use tuning::Tuning;
+
+mod zone;
+use zone::Zones;
+
+mod sink;
+use sink::{BallFilter, Event, EventSink, LogSink, Route, Source};
 
 #[cfg(not(target_os = "windows"))]
 fn os_specific_things() {}
@@ -56,9 +103,35 @@ fn os_specific_things() {
     }
 }
 
+// upper limit of balls launched by emitters (alive at the same time)
+const MAX_EMITTED_BALLS: usize = 64;
+// default UDP port for OSC remote control
+const REMOTE_PORT: u16 = 9000;
+// address the remote control servers listen on: this computer only or the whole network
+const LOCAL_HOST: &str = "127.0.0.1";
+const ANY_HOST: &str = "0.0.0.0";
+// default UDP/TCP port for FUDI messages from pure data
+const FUDI_PORT: u16 = 3001;
+// default TCP port for the WebSocket state stream
+const WEBSOCKET_PORT: u16 = 9002;
+// event sources routed to a MIDI out port of their own: the walls (as in Wall::ALL),
+// wrapping, portals and the attractors
+const PORT_SOURCES: [&str; 7] = [
+    "top",
+    "right",
+    "bottom",
+    "left",
+    "wrap",
+    "teleport",
+    "attractors",
+];
+
 fn main() {
     os_specific_things();
-    nannou::app(model_setup).update(update_handler).run();
+    nannou::app(model_setup)
+        .update(update_handler)
+        .exit(exit_handler)
+        .run();
 }
 
 /// Data structure to hold (persistent) model data to track state.
@@ -66,17 +139,50 @@ struct Model {
     ui: Ui,           // GUI
     widget_ids: Wids, // IDs of the widgets
     midi: MidiConnector,
-    bounce_area_width: f64,
-    bounce_area_height: f64,
-    bounce_area_center_x: f64,
-    bounce_area_center_y: f64,
-    ball_model: BallModel, // model data for the ball
+    pd: PdConnector,                 // pure data output
+    osc: OscConnector,               // OSC output
+    log: LogSink,                    // console output
+    routes: [Route; 4],              // which events go to which backend
+    selected_backend: Backend,       // backend to edit in the output controls
+    port_source: usize,              // event source to route in the output controls
+    attractor_port: String,          // MIDI out port of the attractors ("" = default output)
+    arena: Arena,                    // where the ball bounces
+    physics: Physics,                // forces acting on the ball
+    attractors: Vec<Attractor>,      // points bending the ball trajectory
+    selected_attractor: usize,       // attractor to edit in the attractor controls
+    emitters: Vec<Emitter>,          // launch balls on a rhythm
+    selected_emitter: usize,         // emitter to edit in the emitter controls
+    emitted_balls: Vec<EmittedBall>, // balls launched by the emitters
+    next_ball_id: u64,               // id of the next launched ball (0 = main ball)
+    bpm: f32,                        // tempo to sync movements to
+    selected_wall: Wall,             // wall to edit in the wall controls
+    preview_hits: usize,             // number of hits to predict
+    preview_beats: f32,              // length of the rhythm preview
+    rhythm_preview: Vec<Hit>,        // predicted hits of the ball
+    solver_beats_x: f32,             // beats between hits of the left & right walls
+    solver_beats_y: f32,             // beats between hits of the top & bottom walls
+    ball_model: BallModel,           // model data for the ball
     should_display_about: bool,
     should_display_mit_license: bool,
     state: Option<SaveState>,
-    do_load_state: bool,  // should state be loaded (before doing GUI things?)
-    do_save_state: bool,  // state should be saved (work around borrowing)
-    project_name: String, // name of the project used for loading/saving
+    do_load_state: bool,     // should state be loaded (before doing GUI things?)
+    do_save_state: bool,     // state should be saved (work around borrowing)
+    project_name: String,    // name of the project used for loading/saving
+    playing: bool,           // transport: balls move (or stand still)
+    key: Key,                // all notes are moved into this key
+    custom_scale: String,    // intervals of a custom scale (e.g. "2 2 3 2 3")
+    controllers: String,     // controller values of the selected wall (e.g. "74=100")
+    drum_maps: Vec<DrumMap>, // names of the drums (General MIDI & own maps)
+    drum_map: usize,         // drum map used on the drum channel
+    tuning: Tuning,          // frequencies of the notes
+    tuning_files: String,    // Scala files of the tuning (.scl & .kbm)
+    remote: Option<OscRemote>, // receives OSC commands (if enabled)
+    remote_lan: bool,        // remote control from other computers
+    remote_port: String,     // UDP port to listen on for OSC commands
+    fudi: Option<FudiReceiver>, // receives FUDI commands from pure data (if enabled)
+    fudi_port: String,       // UDP/TCP port to listen on for FUDI commands
+    websocket: Option<StateServer>, // streams the state as JSON (if enabled)
+    websocket_port: String,  // TCP port for the state stream
 }
 
 impl Model {
@@ -96,17 +202,174 @@ impl Model {
             ball_velocity_x,
             ball_velocity_y,
             project_name,
+            virtual_out: self.midi.virtual_out,
+            virtual_out_name: self.midi.virtual_out_name.clone(),
+            virtual_in: self.midi.virtual_in,
+            virtual_in_name: self.midi.virtual_in_name.clone(),
+            midi_out: if self.midi.out_connection.is_some() && !self.midi.virtual_out {
+                self.midi.selected_output.clone()
+            } else {
+                String::new()
+            },
+            midi_outputs: self.midi.outputs.iter().map(|o| o.name.clone()).collect(),
+            wall_ports: Wall::ALL
+                .iter()
+                .map(|wall| self.ball_model.interaction(*wall).midi_port.clone())
+                .collect(),
+            wrap_port: self.ball_model.wrap_interaction.midi_port.clone(),
+            teleport_port: self.ball_model.teleport_interaction.midi_port.clone(),
+            attractor_port: self.attractor_port.clone(),
+            wall_zones: Wall::ALL
+                .iter()
+                .map(|wall| self.ball_model.interaction(*wall).zones)
+                .collect(),
+            wall_programs: Wall::ALL
+                .iter()
+                .map(|wall| self.ball_model.interaction(*wall).program)
+                .collect(),
+            wall_controllers: Wall::ALL
+                .iter()
+                .map(|wall| self.ball_model.interaction(*wall).controllers.clone())
+                .collect(),
+            drum_map: self.drum_maps[self.drum_map].name.clone(),
+            tuning_files: self.tuning_files.clone(),
+            tuning_output: self.midi.retune.output.name().to_string(),
+            tuning_channels: self.midi.retune.channels,
+            reference_frequency: self.tuning.keyboard.reference_frequency,
+            key_root: self.key.root,
+            key_scale: self.key.scale.name.clone(),
+            key_intervals: self.key.scale.intervals(),
         };
         self.state = Some(s);
     }
     /// Overwrite model state with given data
     pub fn overwrite_state(&mut self, state: SaveState) {
+        // no notes of the old project keep sounding
+        release_all(&mut self.midi);
+
         // restore data
         let pos = pt2(state.ball_position_x, state.ball_position_y);
         let vel = pt2(state.ball_velocity_x, state.ball_velocity_y);
         self.ball_model.ball.set_position(pos);
         self.ball_model.ball.set_velocity(vel);
 
+        // virtual MIDI ports (older projects have none)
+        if !state.virtual_out_name.is_empty() {
+            self.midi.virtual_out_name = state.virtual_out_name;
+        }
+        if !state.virtual_in_name.is_empty() {
+            self.midi.virtual_in_name = state.virtual_in_name;
+        }
+        if state.virtual_out {
+            self.midi.virtual_out = open_virtual_output(&mut self.midi);
+        }
+        if state.virtual_in {
+            self.midi.virtual_in = open_virtual_input(&mut self.midi);
+        }
+
+        // MIDI out ports are restored by name, missing ones are reported
+        if !state.midi_out.is_empty() && !state.virtual_out {
+            match open_output_port(&state.midi_out) {
+                Ok(connection) => {
+                    self.midi.out_connection = Some(connection);
+                    self.midi.selected_output = state.midi_out.clone();
+                }
+                Err(e) => eprintln!("MIDI out warning: default output: {}", e),
+            }
+        }
+        for name in state.midi_outputs.iter() {
+            if !open_additional_output(&mut self.midi, name) {
+                eprintln!("MIDI out warning: saved port {} is not available", name);
+            }
+        }
+        for wall in Wall::ALL.iter() {
+            self.ball_model.interaction_mut(*wall).zones = state
+                .wall_zones
+                .get(wall.index())
+                .copied()
+                .unwrap_or(Zones::Off);
+            let interaction = self.ball_model.interaction_mut(*wall);
+            interaction.program = state
+                .wall_programs
+                .get(wall.index())
+                .copied()
+                .unwrap_or(128);
+            interaction.controllers = state
+                .wall_controllers
+                .get(wall.index())
+                .cloned()
+                .unwrap_or_default();
+        }
+        self.controllers =
+            midi::format_controllers(&self.ball_model.interaction(self.selected_wall).controllers);
+        let mut ports = state.wall_ports;
+        ports.resize(Wall::ALL.len(), String::new());
+        ports.extend(vec![
+            state.wrap_port,
+            state.teleport_port,
+            state.attractor_port,
+        ]);
+        for (source, port) in ports.iter().enumerate() {
+            let available = port.is_empty() || self.midi.outputs.iter().any(|o| &o.name == port);
+            if !available {
+                eprintln!(
+                    "MIDI out warning: {} events go to the default output instead of {}",
+                    PORT_SOURCES[source], port
+                );
+            }
+            *source_port(&mut self.ball_model, &mut self.attractor_port, source) = if available {
+                port.clone()
+            } else {
+                String::new()
+            };
+        }
+
+        // the key (older projects have none: chromatic)
+        self.key = Key::new();
+        if !state.key_intervals.is_empty() {
+            match Scale::from_intervals(&state.key_scale, &state.key_intervals) {
+                Ok(scale) => {
+                    self.key = Key {
+                        root: state.key_root % 12,
+                        scale,
+                    }
+                }
+                Err(e) => eprintln!("invalid scale {}: {}", state.key_scale, e),
+            }
+        }
+
+        // the tuning is read from its files again
+        self.tuning_files = state.tuning_files;
+        self.tuning = match Tuning::load(&self.tuning_files) {
+            Ok(tuning) => tuning,
+            Err(e) => {
+                eprintln!("tuning warning: {}, using 12-TET", e);
+                Tuning::standard()
+            }
+        };
+        self.midi.retune.output = TuningOutput::ALL
+            .iter()
+            .copied()
+            .find(|o| o.name() == state.tuning_output)
+            .unwrap_or(TuningOutput::PitchBend);
+        self.midi.retune.channels = state.tuning_channels.max(1).min(16);
+        reset_tunings(&mut self.midi);
+        self.tuning.set_reference(state.reference_frequency); // older projects: 0
+
+        // drum maps are restored by name
+        self.drum_map = 0;
+        if !state.drum_map.is_empty() {
+            match self.drum_maps.iter().position(|m| m.name == state.drum_map) {
+                Some(i) => self.drum_map = i,
+                None => eprintln!(
+                    "drum map warning: {} is not available, using {}",
+                    state.drum_map, self.drum_maps[0].name
+                ),
+            }
+        }
+        self.ball_model
+            .relabel_notes(&self.key, &self.drum_maps[self.drum_map]);
+
         // clear out any old (thus outdated) state
         self.state = None;
     }
@@ -114,14 +377,28 @@ impl Model {
 
 struct Wids {
     midi_out_ports_list: widget::Id, // drop-down list of MIDI out ports
+    midi_in_ports_list: widget::Id,  // drop-down list of MIDI in ports
+    virtual_out_toggle: widget::Id,  // expose a virtual MIDI out port
+    virtual_out_name: widget::Id,    // name of the virtual MIDI out port
+    virtual_in_toggle: widget::Id,   // expose a virtual MIDI in port
+    virtual_in_name: widget::Id,     // name of the virtual MIDI in port
     menue: MenueWidgets,
-    bounce_area: widget::Id,          // where ball can bounce
-    ball_control: BallControlWidgets, // control the ball
+    bounce_area: widget::Id,                    // where ball can bounce
+    ball_control: BallControlWidgets,           // control the ball
+    arena_control: ArenaControlWidgets,         // control the arena
+    physics_control: PhysicsControlWidgets,     // control the forces
+    attractor_control: AttractorControlWidgets, // control the attractors
+    wall_control: WallControlWidgets,           // control the wall behaviour
+    emitter_control: EmitterControlWidgets,     // control the emitters
+    rhythm_control: RhythmControlWidgets,       // control the rhythm preview
+    key_control: KeyControlWidgets,             // control the key
+    output_control: OutputControlWidgets,       // control the outputs
 }
 
 // menue widgets / items
 struct MenueWidgets {
     about_button: widget::Id,
+    panic_button: widget::Id, // stop all notes
     about_text: widget::Id,
     about_text_canvas: widget::Id,
     about_text_close_button: widget::Id,
@@ -132,6 +409,14 @@ struct MenueWidgets {
     save_button: widget::Id,
     load_button: widget::Id,
     project_name_textbox: widget::Id,
+    play_button: widget::Id,            // start / stop the transport
+    remote_toggle: widget::Id,          // enable OSC remote control
+    fudi_toggle: widget::Id,            // enable FUDI remote control
+    remote_lan_toggle: widget::Id,      // accept remote control from other computers
+    remote_port_textbox: widget::Id,    // UDP port for OSC commands
+    fudi_port_textbox: widget::Id,      // UDP/TCP port for FUDI commands
+    websocket_toggle: widget::Id,       // enable the WebSocket state stream
+    websocket_port_textbox: widget::Id, // TCP port for the state stream
 }
 
 // all things ball control widgets
@@ -145,6 +430,115 @@ struct BallControlWidgets {
     left: BallInteractionControlWidgets,   // interaction with left border
 }
 
+// all things arena control widgets
+struct ArenaControlWidgets {
+    canvas: widget::Id,           // canvas for arena controls
+    bpm: widget::Id,              // tempo
+    rotation_rate: widget::Id,    // rotation speed of the whole arena
+    rotation_synced: widget::Id,  // sync rotation to tempo
+    wall_select: widget::Id,      // wall to edit
+    wall_amplitude: widget::Id,   // oscillation amplitude of selected wall
+    wall_rate: widget::Id,        // oscillation speed of selected wall
+    wall_synced: widget::Id,      // sync oscillation to tempo
+    wall_restitution: widget::Id, // bounciness of selected wall
+}
+
+// all things physics control widgets
+struct PhysicsControlWidgets {
+    canvas: widget::Id,           // canvas for physics controls
+    gravity: widget::Id,          // strength of gravity
+    gravity_angle: widget::Id,    // direction of gravity
+    drag: widget::Id,             // air drag
+    rekick: widget::Id,           // toggle re-kick of slow balls
+    rekick_threshold: widget::Id, // speed regarded as too slow
+    rekick_speed: widget::Id,     // speed after a kick
+}
+
+// all things attractor control widgets
+struct AttractorControlWidgets {
+    canvas: widget::Id,         // canvas for attractor controls
+    add_button: widget::Id,     // add a new attractor
+    remove_button: widget::Id,  // remove selected attractor
+    select: widget::Id,         // attractor to edit
+    x: widget::Id,              // horizontal position
+    y: widget::Id,              // vertical position
+    strength: widget::Id,       // attract (positive) or repel (negative)
+    falloff: widget::Id,        // distance falloff exponent
+    trigger_radius: widget::Id, // distance to trigger a note
+    note: widget::Id,           // note to trigger
+}
+
+// all things wall behaviour control widgets
+struct WallControlWidgets {
+    canvas: widget::Id,          // canvas for wall controls
+    behaviour: widget::Id,       // what happens at the selected wall
+    portal_target: widget::Id,   // exit wall of a portal
+    portal_rotation: widget::Id, // rotation when passing a portal
+    wrap_note: widget::Id,       // note to play when wrapping around
+    teleport_note: widget::Id,   // note to play when passing a portal
+    pitch_mode: widget::Id,      // fixed note, zones or glide along the wall
+    zone_count: widget::Id,      // number of zones (or semitones to glide)
+    program: widget::Id,         // program change sent with each note
+    controllers: widget::Id,     // controller values sent with each note
+}
+
+// all things emitter control widgets
+struct EmitterControlWidgets {
+    canvas: widget::Id,        // canvas for emitter controls
+    add_button: widget::Id,    // add a new emitter
+    remove_button: widget::Id, // remove selected emitter
+    select: widget::Id,        // emitter to edit
+    x: widget::Id,             // horizontal position
+    y: widget::Id,             // vertical position
+    rate: widget::Id,          // balls per beat
+    direction: widget::Id,     // launch direction
+    spread: widget::Id,        // random deviation of the direction
+    speed: widget::Id,         // launch speed
+    speed_spread: widget::Id,  // random deviation of the speed
+    lifetime: widget::Id,      // seconds until a ball is removed
+    max_hits: widget::Id,      // hits until a ball is removed
+    on_midi: widget::Id,       // launch on incoming MIDI notes
+}
+
+// all things rhythm preview widgets
+struct RhythmControlWidgets {
+    canvas: widget::Id,       // canvas for the rhythm preview
+    hits: widget::Id,         // number of hits to predict
+    beats: widget::Id,        // length of the preview
+    solve_x: widget::Id,      // target beats between left & right hits
+    solve_y: widget::Id,      // target beats between top & bottom hits
+    solve_button: widget::Id, // set the velocity playing the target rhythm
+}
+
+// all things key widgets
+struct KeyControlWidgets {
+    root: widget::Id,            // root of the key
+    scale: widget::Id,           // scale of the key
+    custom: widget::Id,          // intervals of a custom scale
+    drums: widget::Id,           // drum map for the drum channel
+    tuning_files: widget::Id,    // Scala files of the tuning
+    tuning_output: widget::Id,   // pitch bend or MTS
+    tuning_channels: widget::Id, // channels bent notes take turns on
+    reference: widget::Id,       // frequency of the reference note
+}
+
+// all things output control widgets
+struct OutputControlWidgets {
+    canvas: widget::Id,         // canvas for output controls
+    select: widget::Id,         // backend to edit
+    active: widget::Id,         // send events to the backend
+    target: widget::Id,         // host:port of pure data / OSC receiver
+    bundles: widget::Id,        // send OSC bundles with timetags
+    walls: [widget::Id; 4],     // route the events of the individual walls
+    attractors: widget::Id,     // route the events of the attractors
+    balls: widget::Id,          // route the events of some balls only
+    ports: widget::Id,          // open / close additional MIDI out ports
+    port_source: widget::Id,    // event source to choose the MIDI out port for
+    source_port: widget::Id,    // MIDI out port of the chosen event source
+    mpe: widget::Id,            // a MIDI channel per note (MPE)
+    mpe_expression: widget::Id, // what drives timbre & pressure of MPE notes
+}
+
 // how the ball interacts
 struct BallInteractionControlWidgets {
     widget_canvas: widget::Id, // controls for top border
@@ -163,119 +557,247 @@ struct BallModel {
     right_border_interaction: BallInteractionModel,
     bottom_border_interaction: BallInteractionModel,
     left_border_interaction: BallInteractionModel,
+    wrap_interaction: BallInteractionModel, // wrapping around to the opposite wall
+    teleport_interaction: BallInteractionModel, // passing a portal
 }
 
-struct BallInteractionModel {
-    note_display: String, // note to play when ball hits
-    midi_note: u8,        // MIDI note to play
-    velocity: u8,         // MIDI velocity
-    length: u64,          // note length in ms
-    midi_channel: u8,     // MIDI channel to send data on
-}
-// all things MIDI
-struct MidiConnector {
-    out_port_number: usize,
-    out_usable: bool,                            // guard for MidiOutputConnection
-    out_connection: midir::MidiOutputConnection, // option seems more intuitive but leads to borrowing issues
-    selected_output: String,                     // currently selected MIDI output
-    time_queue: BinaryHeap<TimedMidiMessage>,    // (sorted) queue of timestamps to trigger events
-}
+impl BallInteractionModel {
+    /// Set the note to play, as chosen in the given key.
+    pub fn set_note(&mut self, note: u8, key: &Key, drums: &DrumMap) {
+        self.midi_note = note;
+        self.note_root = key.root;
+        self.relabel(key, drums);
+    }
 
-// a struct to hold timing information and MIDI data
-// (to be put in a queue) ... all this to avoid lifetime
-// issues with the Model struct (when using wmidi)
-// *note* Eq, Order etc. are based soley on the timestamps
-#[derive(Hash)]
-struct TimedMidiMessage {
-    r#type: TimedMidiMessageType,
-    note: u8,            // MIDI note
-    channel: u8,         // MIDI channel
-    velocity: u8,        // note velocity
-    timestamp: Duration, // when to trigger
-}
+    /// The note played in the given key: the chosen note moved into it,
+    /// unless it is a drum.
+    pub fn note(&self, key: &Key) -> u8 {
+        if self.is_drum() {
+            self.midi_note
+        } else {
+            key.transpose_from(self.note_root, self.midi_note)
+        }
+    }
 
-// qualify the type of TimedMidiMessage
-#[derive(Hash, PartialEq)]
-enum TimedMidiMessageType {
-    NoteOn,
-    NoteOff,
-}
+    /// Name the note played in the given key (as drum or note).
+    pub fn relabel(&mut self, key: &Key, drums: &DrumMap) {
+        let note = self.note(key);
+        self.note_display = match drums.name_of(note).filter(|_| self.is_drum()) {
+            Some(name) => name.to_string(),
+            None => mididata::note_name(note),
+        };
+    }
 
-impl PartialEq for TimedMidiMessage {
-    fn eq(&self, other: &Self) -> bool {
-        self.timestamp == other.timestamp
+    /// Check if the notes trigger drums (sent on the drum channel).
+    pub fn is_drum(&self) -> bool {
+        self.midi_channel == drums::CHANNEL
     }
 }
 
-impl Eq for TimedMidiMessage {}
+impl BallModel {
+    /// Interaction for hitting the given wall.
+    pub fn interaction(&self, wall: Wall) -> &BallInteractionModel {
+        match wall {
+            Wall::Top => &self.top_border_interaction,
+            Wall::Right => &self.right_border_interaction,
+            Wall::Bottom => &self.bottom_border_interaction,
+            Wall::Left => &self.left_border_interaction,
+        }
+    }
+
+    /// Interaction for hitting the given wall (to change it).
+    pub fn interaction_mut(&mut self, wall: Wall) -> &mut BallInteractionModel {
+        match wall {
+            Wall::Top => &mut self.top_border_interaction,
+            Wall::Right => &mut self.right_border_interaction,
+            Wall::Bottom => &mut self.bottom_border_interaction,
+            Wall::Left => &mut self.left_border_interaction,
+        }
+    }
 
-impl PartialOrd for TimedMidiMessage {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if self.timestamp < other.timestamp {
-            return Some(Ordering::Greater); // needs to be processed earlier
+    /// Name the notes anew (e.g. after switching to another drum map).
+    pub fn relabel_notes(&mut self, key: &Key, drums: &DrumMap) {
+        for interaction in [
+            &mut self.top_border_interaction,
+            &mut self.right_border_interaction,
+            &mut self.bottom_border_interaction,
+            &mut self.left_border_interaction,
+            &mut self.wrap_interaction,
+            &mut self.teleport_interaction,
+        ]
+        .iter_mut()
+        {
+            interaction.relabel(key, drums);
         }
-        if self.timestamp > other.timestamp {
-            return Some(Ordering::Less); // neds to be processed later
+    }
+
+    /// Interaction for what happened at a wall.
+    pub fn hit_interaction(&self, hit: &Hit) -> &BallInteractionModel {
+        match hit.kind {
+            HitKind::Bounce | HitKind::Absorb => self.interaction(hit.wall),
+            HitKind::Wrap => &self.wrap_interaction,
+            HitKind::Teleport => &self.teleport_interaction,
         }
-        return Some(Ordering::Equal);
     }
 }
 
-impl Ord for TimedMidiMessage {
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self.timestamp < other.timestamp {
-            return Ordering::Greater; // needs to be processed earlier
+struct BallInteractionModel {
+    note_display: String,       // note to play when ball hits
+    midi_note: u8,              // MIDI note to play (as chosen)
+    note_root: u8,              // root of the key the note was chosen in
+    velocity: u8,               // MIDI velocity
+    length: u64,                // note length in ms
+    midi_channel: u8,           // MIDI channel to send data on
+    midi_port: String,          // MIDI out port to send to ("" = default output)
+    program: u8,                // program change sent with each note (128 = none)
+    controllers: Vec<(u8, u8)>, // controller values sent with each note
+    zones: Zones,               // pitch depending on where the wall is hit
+}
+/// Where collisions are sent to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Backend {
+    Midi, // MIDI out port
+    Pd,   // pure data via FUDI
+    Osc,  // Open Sound Control
+    Log,  // console
+}
+
+impl Backend {
+    pub const ALL: [Backend; 4] = [Backend::Midi, Backend::Pd, Backend::Osc, Backend::Log];
+
+    // Position of the backend in per-backend arrays.
+    pub fn index(&self) -> usize {
+        match self {
+            Backend::Midi => 0,
+            Backend::Pd => 1,
+            Backend::Osc => 2,
+            Backend::Log => 3,
         }
-        if self.timestamp > other.timestamp {
-            return Ordering::Less; // needs to be processed later
+    }
+
+    // Human readable name of the backend.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Midi => "MIDI",
+            Backend::Pd => "Pd",
+            Backend::Osc => "OSC",
+            Backend::Log => "log",
         }
-        return Ordering::Equal;
     }
 }
 
-impl TimedMidiMessage {
-    /// Create note on & off MIDI message.
-    pub fn create_on_off(
-        chan: u8,
-        note: u8,
-        velocity: u8,
-        trigger: Duration,
-        length: Duration,
-    ) -> (TimedMidiMessage, TimedMidiMessage) {
-        let on = TimedMidiMessage {
-            r#type: TimedMidiMessageType::NoteOn,
-            note: note,
-            channel: chan,
-            velocity,
-            timestamp: trigger,
-        };
-        let off = TimedMidiMessage {
-            r#type: TimedMidiMessageType::NoteOff,
-            note: note,
-            channel: chan,
-            velocity: 0,
-            timestamp: trigger + length,
-        };
-        return (on, off);
-    }
-
-    /// Convert to MIDI message to send
-    pub fn to_bytes(self) -> Vec<u8> {
-        let mut msg = wmidi::MidiMessage::TuneRequest; // gets reassinged anyway
-        let c = wmidi::Channel::from_index(self.channel).expect("could not convert channel");
-        unsafe {
-            let n = wmidi::Note::from_u8_unchecked(self.note);
-            let v = wmidi::U7::from_unchecked(self.velocity);
-            if self.r#type == TimedMidiMessageType::NoteOn {
-                msg = wmidi::MidiMessage::NoteOn(c, n, v);
-            }
-            if self.r#type == TimedMidiMessageType::NoteOff {
-                msg = wmidi::MidiMessage::NoteOff(c, n, v);
+// all things pure data
+struct PdConnector {
+    target: String,       // host:port of the [netreceive] in pd
+    ball: Option<PdBall>, // sends the messages (if connected)
+}
+
+// all things OSC
+struct OscConnector {
+    target: String,        // host:port of the OSC receiver
+    ball: Option<OscBall>, // sends the messages (if connected)
+    bundles: bool,         // send timetagged bundles instead of plain messages
+}
+
+// all things MIDI
+/// An additional open MIDI out port.
+struct MidiPort {
+    name: String, // name of the port (as listed by the system)
+    connection: midir::MidiOutputConnection,
+}
+
+struct MidiConnector {
+    out_port_number: usize,
+    out_connection: Option<MidiOutputConnection>, // default output (if any)
+    selected_output: String,                      // currently selected MIDI output
+    time_queue: MidiQueue,                        // (sorted) queue of timestamps to trigger events
+    in_connection: Option<midir::MidiInputConnection<mpsc::Sender<u8>>>, // MIDI input (if any)
+    selected_input: String,                       // currently selected MIDI input
+    in_sender: mpsc::Sender<u8>,                  // passes incoming notes from the MIDI thread
+    in_receiver: mpsc::Receiver<u8>,              // incoming notes
+    virtual_out: bool,                            // own output port other apps connect to
+    virtual_out_name: String,                     // name of the virtual output port
+    virtual_in: bool,                             // own input port other apps connect to
+    virtual_in_name: String,                      // name of the virtual input port
+    outputs: Vec<MidiPort>,                       // additional open output ports
+    active_notes: ActiveNotes,                    // notes sent but not stopped yet
+    mpe: Mpe,                                     // a member channel per note (if active)
+    retune: Retune,                               // output of notes between the keys
+    bends: Bends,                                 // last pitch bend of every channel
+    tunings: Tunings,                             // keys retuned via MTS
+}
+
+// MIDI events are queued and sent when they are due
+impl EventSink for MidiConnector {
+    fn name(&self) -> String {
+        "MIDI".to_string()
+    }
+
+    fn send(&mut self, event: &Event, _now: Duration) -> std::io::Result<()> {
+        let mut channel = event.channel;
+        let mut note = event.note;
+        let mut setup = Vec::<TimedMidiMessageType>::new();
+        // a note between the keys (gliding or tuned) is either its key tuned via
+        // MTS or the nearest key, bent on a channel of its own
+        let mut bend = None;
+        if let Some(semitones) = event.bend {
+            let pitch = event.note as f32 + semitones;
+            if self.retune.output == TuningOutput::Mts && !self.mpe.active {
+                let tuning = midi::note_tuning(event.note, pitch);
+                self.tunings.tune(&event.port, event.note, tuning);
+                setup.push(tuning);
+            } else {
+                let (key, rest) = midi::nearest_key(pitch);
+                note = key;
+                bend = Some(rest);
             }
+        } else if self.tunings.reset(&event.port, event.note) {
+            // an unbent note on a key tuned for a previous note
+            setup.push(midi::note_tuning(event.note, event.note as f32));
+        }
+        let drum = event.channel == drums::CHANNEL; // drums stay on their channel
+        if self.mpe.active && !drum {
+            // the expression of the note is set on its own channel right before it starts
+            channel = self.mpe.next_channel();
+            let (bend, timbre, pressure) = self.mpe.expression(event, bend);
+            self.bends.bend(&event.port, channel, bend);
+            setup.push(TimedMidiMessageType::PitchBend(bend));
+            setup.push(TimedMidiMessageType::ControlChange(midi::TIMBRE, timbre));
+            setup.push(TimedMidiMessageType::ChannelPressure(pressure));
+        } else if let Some(semitones) = bend {
+            channel = self.retune.next_channel(event.channel);
+            let bend = midi::pitch_bend(semitones / midi::BEND_RANGE);
+            self.bends.bend(&event.port, channel, bend);
+            setup.push(TimedMidiMessageType::PitchBend(bend));
+        } else if self.bends.center(&event.port, channel) {
+            // an unbent note after a bent one on the same channel
+            setup.push(TimedMidiMessageType::PitchBend(midi::BEND_CENTER));
+        }
+        // a program change or controllers set the sound right before the note
+        if let Some(program) = event.program {
+            setup.push(TimedMidiMessageType::ProgramChange(program));
+        }
+        for (controller, value) in event.controllers.iter() {
+            setup.push(TimedMidiMessageType::ControlChange(*controller, *value));
+        }
+        for r#type in setup {
+            self.time_queue.push(TimedMidiMessage::new(
+                r#type,
+                &event.port,
+                channel,
+                event.time,
+            ));
         }
-        let mut bytes = vec![0u8; msg.bytes_size()];
-        msg.copy_to_slice(bytes.as_mut_slice()).unwrap();
-        return bytes;
+        queue_note(
+            self,
+            &event.port,
+            channel,
+            note,
+            event.velocity,
+            event.time,
+            event.length,
+        );
+        Ok(())
     }
 }
 
@@ -287,16 +809,60 @@ struct SaveState {
     ball_velocity_x: f32,
     ball_velocity_y: f32,
     project_name: String,
+    #[serde(default)]
+    virtual_out: bool,
+    #[serde(default)]
+    virtual_out_name: String,
+    #[serde(default)]
+    virtual_in: bool,
+    #[serde(default)]
+    virtual_in_name: String,
+    #[serde(default)]
+    midi_out: String, // default output (by port name)
+    #[serde(default)]
+    midi_outputs: Vec<String>, // additional outputs (by port name)
+    #[serde(default)]
+    wall_ports: Vec<String>, // output of each wall (top, right, bottom, left)
+    #[serde(default)]
+    wrap_port: String, // output of wrapping around
+    #[serde(default)]
+    teleport_port: String, // output of passing a portal
+    #[serde(default)]
+    attractor_port: String, // output of the attractors
+    #[serde(default)]
+    wall_zones: Vec<Zones>, // pitch zones of each wall (top, right, bottom, left)
+    #[serde(default)]
+    wall_programs: Vec<u8>, // program change of each wall (128 = none)
+    #[serde(default)]
+    wall_controllers: Vec<Vec<(u8, u8)>>, // controller values of each wall
+    #[serde(default)]
+    drum_map: String, // name of the drum map (none = General MIDI)
+    #[serde(default)]
+    tuning_files: String, // Scala files of the tuning (none = 12-TET)
+    #[serde(default)]
+    tuning_output: String, // how tuned notes are sent via MIDI
+    #[serde(default)]
+    tuning_channels: u8, // channels bent notes take turns on
+    #[serde(default)]
+    reference_frequency: f32, // Hz of the reference note (0 = as in the tuning files)
+    #[serde(default)]
+    key_root: u8, // pitch class of the root (0 = C)
+    #[serde(default)]
+    key_scale: String, // name of the scale
+    #[serde(default)]
+    key_intervals: Vec<u8>, // intervals of the scale (none = chromatic)
 }
 
 /// Create the initial model / state of the application.
 fn model_setup(app: &App) -> Model {
-    app.set_loop_mode(LoopMode::rate_fps(60.0)); // fixed updates at 60 fps
+    // fixed updates at 60 fps
+    app.set_loop_mode(LoopMode::rate_fps(rhythm::UPDATES_PER_SECOND as f64));
 
     // set up the application window
     let _window = app
         .new_window()
         .title(format!("bouncyquencer"))
+        .size(1440, 900)
         .view(view_handler)
         .event(window_event_handler)
         .build()
@@ -339,8 +905,14 @@ fn model_setup(app: &App) -> Model {
     };
     let widget_ids = Wids {
         midi_out_ports_list: ui.generate_widget_id(),
+        midi_in_ports_list: ui.generate_widget_id(),
+        virtual_out_toggle: ui.generate_widget_id(),
+        virtual_out_name: ui.generate_widget_id(),
+        virtual_in_toggle: ui.generate_widget_id(),
+        virtual_in_name: ui.generate_widget_id(),
         menue: MenueWidgets {
             about_button: ui.generate_widget_id(),
+            panic_button: ui.generate_widget_id(),
             about_text: ui.generate_widget_id(),
             about_text_canvas: ui.generate_widget_id(),
             about_text_close_button: ui.generate_widget_id(),
@@ -351,15 +923,118 @@ fn model_setup(app: &App) -> Model {
             save_button: ui.generate_widget_id(),
             load_button: ui.generate_widget_id(),
             project_name_textbox: ui.generate_widget_id(),
+            play_button: ui.generate_widget_id(),
+            remote_toggle: ui.generate_widget_id(),
+            fudi_toggle: ui.generate_widget_id(),
+            remote_lan_toggle: ui.generate_widget_id(),
+            remote_port_textbox: ui.generate_widget_id(),
+            fudi_port_textbox: ui.generate_widget_id(),
+            websocket_toggle: ui.generate_widget_id(),
+            websocket_port_textbox: ui.generate_widget_id(),
         },
         bounce_area: ui.generate_widget_id(),
         ball_control,
+        arena_control: ArenaControlWidgets {
+            canvas: ui.generate_widget_id(),
+            bpm: ui.generate_widget_id(),
+            rotation_rate: ui.generate_widget_id(),
+            rotation_synced: ui.generate_widget_id(),
+            wall_select: ui.generate_widget_id(),
+            wall_amplitude: ui.generate_widget_id(),
+            wall_rate: ui.generate_widget_id(),
+            wall_synced: ui.generate_widget_id(),
+            wall_restitution: ui.generate_widget_id(),
+        },
+        physics_control: PhysicsControlWidgets {
+            canvas: ui.generate_widget_id(),
+            gravity: ui.generate_widget_id(),
+            gravity_angle: ui.generate_widget_id(),
+            drag: ui.generate_widget_id(),
+            rekick: ui.generate_widget_id(),
+            rekick_threshold: ui.generate_widget_id(),
+            rekick_speed: ui.generate_widget_id(),
+        },
+        attractor_control: AttractorControlWidgets {
+            canvas: ui.generate_widget_id(),
+            add_button: ui.generate_widget_id(),
+            remove_button: ui.generate_widget_id(),
+            select: ui.generate_widget_id(),
+            x: ui.generate_widget_id(),
+            y: ui.generate_widget_id(),
+            strength: ui.generate_widget_id(),
+            falloff: ui.generate_widget_id(),
+            trigger_radius: ui.generate_widget_id(),
+            note: ui.generate_widget_id(),
+        },
+        wall_control: WallControlWidgets {
+            canvas: ui.generate_widget_id(),
+            behaviour: ui.generate_widget_id(),
+            portal_target: ui.generate_widget_id(),
+            portal_rotation: ui.generate_widget_id(),
+            wrap_note: ui.generate_widget_id(),
+            teleport_note: ui.generate_widget_id(),
+            pitch_mode: ui.generate_widget_id(),
+            zone_count: ui.generate_widget_id(),
+            program: ui.generate_widget_id(),
+            controllers: ui.generate_widget_id(),
+        },
+        emitter_control: EmitterControlWidgets {
+            canvas: ui.generate_widget_id(),
+            add_button: ui.generate_widget_id(),
+            remove_button: ui.generate_widget_id(),
+            select: ui.generate_widget_id(),
+            x: ui.generate_widget_id(),
+            y: ui.generate_widget_id(),
+            rate: ui.generate_widget_id(),
+            direction: ui.generate_widget_id(),
+            spread: ui.generate_widget_id(),
+            speed: ui.generate_widget_id(),
+            speed_spread: ui.generate_widget_id(),
+            lifetime: ui.generate_widget_id(),
+            max_hits: ui.generate_widget_id(),
+            on_midi: ui.generate_widget_id(),
+        },
+        key_control: KeyControlWidgets {
+            root: ui.generate_widget_id(),
+            scale: ui.generate_widget_id(),
+            custom: ui.generate_widget_id(),
+            drums: ui.generate_widget_id(),
+            tuning_files: ui.generate_widget_id(),
+            tuning_output: ui.generate_widget_id(),
+            tuning_channels: ui.generate_widget_id(),
+            reference: ui.generate_widget_id(),
+        },
+        rhythm_control: RhythmControlWidgets {
+            canvas: ui.generate_widget_id(),
+            hits: ui.generate_widget_id(),
+            beats: ui.generate_widget_id(),
+            solve_x: ui.generate_widget_id(),
+            solve_y: ui.generate_widget_id(),
+            solve_button: ui.generate_widget_id(),
+        },
+        output_control: OutputControlWidgets {
+            canvas: ui.generate_widget_id(),
+            select: ui.generate_widget_id(),
+            active: ui.generate_widget_id(),
+            target: ui.generate_widget_id(),
+            bundles: ui.generate_widget_id(),
+            walls: [
+                ui.generate_widget_id(),
+                ui.generate_widget_id(),
+                ui.generate_widget_id(),
+                ui.generate_widget_id(),
+            ],
+            attractors: ui.generate_widget_id(),
+            balls: ui.generate_widget_id(),
+            ports: ui.generate_widget_id(),
+            port_source: ui.generate_widget_id(),
+            source_port: ui.generate_widget_id(),
+            mpe: ui.generate_widget_id(),
+            mpe_expression: ui.generate_widget_id(),
+        },
     };
 
-    let bounce_area_width = 200.0;
-    let bounce_area_height = 200.0;
-    let bounce_area_center_x = 300.0;
-    let bounce_area_center_y = 0.0;
+    let arena = Arena::new(pt2(300.0, 0.0), 200.0, 200.0);
 
     // the bouncy ball
     let mut ball = Ball::new();
@@ -369,10 +1044,7 @@ fn model_setup(app: &App) -> Model {
         87.0 / 255.0,
         1.0,
     ));
-    ball.set_position(pt2(
-        bounce_area_center_x as f32,
-        bounce_area_center_y as f32,
-    ));
+    ball.set_position(arena.center);
     ball.set_radius(15.0);
     let v = ball.get_velocity();
 
@@ -383,42 +1055,97 @@ fn model_setup(app: &App) -> Model {
         top_border_interaction: BallInteractionModel {
             note_display: "None".to_string(),
             midi_note: 128, // outside MIDI note range
+            note_root: 0,
             velocity: 64,
             length: 100, // at least 10 ms
             midi_channel: 1,
+            midi_port: String::new(),
+            program: 128,
+            controllers: vec![],
+            zones: Zones::Off,
         },
         right_border_interaction: BallInteractionModel {
             note_display: "None".to_string(),
             midi_note: 128,
+            note_root: 0,
             velocity: 64,
             length: 100,
             midi_channel: 1,
+            midi_port: String::new(),
+            program: 128,
+            controllers: vec![],
+            zones: Zones::Off,
         },
         bottom_border_interaction: BallInteractionModel {
             note_display: "None".to_string(),
             midi_note: 128,
+            note_root: 0,
             velocity: 64,
             length: 100,
             midi_channel: 1,
+            midi_port: String::new(),
+            program: 128,
+            controllers: vec![],
+            zones: Zones::Off,
         },
         left_border_interaction: BallInteractionModel {
             note_display: "None".to_string(),
             midi_note: 128,
+            note_root: 0,
+            velocity: 64,
+            length: 100,
+            midi_channel: 1,
+            midi_port: String::new(),
+            program: 128,
+            controllers: vec![],
+            zones: Zones::Off,
+        },
+        wrap_interaction: BallInteractionModel {
+            note_display: "None".to_string(),
+            midi_note: 128,
+            note_root: 0,
             velocity: 64,
             length: 100,
             midi_channel: 1,
+            midi_port: String::new(),
+            program: 128,
+            controllers: vec![],
+            zones: Zones::Off,
+        },
+        teleport_interaction: BallInteractionModel {
+            note_display: "None".to_string(),
+            midi_note: 128,
+            note_root: 0,
+            velocity: 64,
+            length: 100,
+            midi_channel: 1,
+            midi_port: String::new(),
+            program: 128,
+            controllers: vec![],
+            zones: Zones::Off,
         },
     };
     // all things MIDI
+    let (in_sender, in_receiver) = mpsc::channel::<u8>();
     let midi = MidiConnector {
         out_port_number: 0,
-        out_usable: false,
-        out_connection: midir::MidiOutput::new("dummy MIDI out")
-            .expect("failed to create MIDI output")
-            .connect(0, "")
-            .expect("could not create dummy connection"),
+        out_connection: None,
         selected_output: "no MIDI out selected".to_string(),
-        time_queue: BinaryHeap::<TimedMidiMessage>::new(),
+        time_queue: MidiQueue::new(),
+        in_connection: None,
+        selected_input: "no MIDI in selected".to_string(),
+        in_sender,
+        in_receiver,
+        virtual_out: false,
+        virtual_out_name: "bouncyquencer out".to_string(),
+        virtual_in: false,
+        virtual_in_name: "bouncyquencer in".to_string(),
+        outputs: vec![],
+        active_notes: ActiveNotes::new(),
+        mpe: Mpe::new(),
+        retune: Retune::new(),
+        bends: Bends::new(),
+        tunings: Tunings::new(),
     };
 
     // set up the model
@@ -426,10 +1153,43 @@ fn model_setup(app: &App) -> Model {
         ui,
         widget_ids,
         midi,
-        bounce_area_width,
-        bounce_area_height,
-        bounce_area_center_x,
-        bounce_area_center_y,
+        pd: PdConnector {
+            target: "127.0.0.1:3000".to_string(),
+            ball: None,
+        },
+        osc: OscConnector {
+            target: "127.0.0.1:57120".to_string(),
+            ball: None,
+            bundles: false,
+        },
+        log: LogSink,
+        routes: [
+            Route {
+                active: true, // MIDI is on by default
+                ..Route::new()
+            },
+            Route::new(),
+            Route::new(),
+            Route::new(),
+        ],
+        selected_backend: Backend::Midi,
+        port_source: 0,
+        attractor_port: String::new(),
+        arena,
+        physics: Physics::new(),
+        attractors: Vec::<Attractor>::new(),
+        selected_attractor: 0,
+        emitters: Vec::<Emitter>::new(),
+        selected_emitter: 0,
+        emitted_balls: Vec::<EmittedBall>::new(),
+        next_ball_id: 1,
+        bpm: 120.0,
+        selected_wall: Wall::Top,
+        preview_hits: 16,
+        preview_beats: 4.0,
+        rhythm_preview: Vec::<Hit>::new(),
+        solver_beats_x: 3.0,
+        solver_beats_y: 4.0,
         ball_model,
         should_display_about: false,
         should_display_mit_license: false,
@@ -437,6 +1197,21 @@ fn model_setup(app: &App) -> Model {
         do_load_state: false,
         do_save_state: false,
         project_name: "type project name ...".to_string(),
+        playing: true,
+        key: Key::new(),
+        custom_scale: "2 2 3 2 3".to_string(),
+        controllers: String::new(),
+        drum_maps: drums::load_all(Path::new(".")), // next to the projects
+        drum_map: 0,
+        tuning: Tuning::standard(),
+        tuning_files: String::new(),
+        remote: None,
+        remote_lan: false,
+        remote_port: REMOTE_PORT.to_string(),
+        fudi: None,
+        fudi_port: FUDI_PORT.to_string(),
+        websocket: None,
+        websocket_port: WEBSOCKET_PORT.to_string(),
     };
 
     // set up MIDI output
@@ -449,6 +1224,11 @@ fn model_setup(app: &App) -> Model {
     return model;
 }
 
+/// Stop all sounding notes when the application closes.
+fn exit_handler(_app: &App, mut model: Model) {
+    release_all(&mut model.midi);
+}
+
 /// Handle window events to change the world model.
 fn window_event_handler(_app: &App, _model: &mut Model, event: WindowEvent) {
     match event {
@@ -458,6 +1238,37 @@ fn window_event_handler(_app: &App, _model: &mut Model, event: WindowEvent) {
 
 /// Handle updates to change the world model and the GUI.
 fn update_handler(_app: &App, model: &mut Model, update: Update) {
+    // remote control (before the GUI shows the changes)
+    let commands = match &model.remote {
+        Some(remote) => remote.poll(),
+        None => vec![],
+    };
+    for command in commands {
+        match command {
+            Ok(c) => apply_command(model, c),
+            Err(e) => eprintln!("OSC remote error: {}", e),
+        }
+    }
+    let commands = match model.fudi.as_mut() {
+        Some(fudi) => fudi.poll(),
+        None => vec![],
+    };
+    for command in commands {
+        match command {
+            Ok(c) => apply_command(model, c),
+            Err(e) => eprintln!("FUDI error: {}", e),
+        }
+    }
+    let commands = match model.websocket.as_mut() {
+        Some(websocket) => websocket.poll(),
+        None => vec![],
+    };
+    for command in commands {
+        match command {
+            Ok(c) => apply_command(model, c),
+            Err(e) => eprintln!("WebSocket error: {}", e),
+        }
+    }
     if model.do_load_state {
         load_model(model);
         model.do_load_state = false;
@@ -472,13 +1283,13 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
     // --- begin GUI code --- //
     let ui = &mut model.ui.set_widgets(); // instantiate widgets
 
-    // bounce area for the ball
-    let barea = widget::BorderedRectangle::new([model.bounce_area_width, model.bounce_area_height])
-        .x(model.bounce_area_center_x)
-        .y(model.bounce_area_center_y)
-        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 0.3)
-        .border(2.0)
-        .border_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.5));
+    // bounce area for the ball (invisible, the moving walls are drawn in the view)
+    let barea =
+        widget::BorderedRectangle::new([model.arena.width as f64, model.arena.height as f64])
+            .x(model.arena.center.x as f64)
+            .y(model.arena.center.y as f64)
+            .rgba(0.0, 0.0, 0.0, 0.0)
+            .border(0.0);
     barea.set(model.widget_ids.bounce_area, ui);
 
     // list of notes
@@ -486,6 +1297,12 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
     for n in mididata::MIDINOTES.iter().map(|tuple| tuple.0) {
         notenames.push(n);
     }
+    // list of drums (for walls on the drum channel)
+    let drum_map = &model.drum_maps[model.drum_map];
+    let mut drumnames = vec!["None"];
+    drumnames.extend(drum_map.drums.iter().map(|d| d.name.as_str()));
+    let mut drumnotes = vec![128u8];
+    drumnotes.extend(drum_map.drums.iter().map(|d| d.note));
 
     //-- start: left control canvas
     widget::Canvas::new()
@@ -517,6 +1334,10 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
         .set(model.widget_ids.ball_control.left.channel, ui)
     {
         model.ball_model.left_border_interaction.midi_channel = (i + 1).try_into().unwrap();
+        model
+            .ball_model
+            .left_border_interaction
+            .relabel(&model.key, drum_map); // named as drum or note
     }
 
     // velocity
@@ -568,7 +1389,8 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
     widget_offset = widget_offset
         + ui.wh_of(model.widget_ids.ball_control.left.length).unwrap()[1]
         + stack_space;
-    for i in widget::DropDownList::new(&notenames, None)
+    let drum = model.ball_model.left_border_interaction.is_drum();
+    for i in widget::DropDownList::new(if drum { &drumnames } else { &notenames }, None)
         .mid_bottom_with_margin_on(
             model.widget_ids.ball_control.left.widget_canvas,
             widget_offset,
@@ -584,9 +1406,15 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
     // attach widget to UI
     {
         // process selection index
-        model.ball_model.left_border_interaction.note_display = notenames[i].to_string().clone();
-        model.ball_model.left_border_interaction.midi_note = (72 - i).try_into().unwrap();
-        // 72 = max MIDI note value
+        let note = if drum {
+            drumnotes[i]
+        } else {
+            mididata::MIDINOTES[i].1
+        };
+        model
+            .ball_model
+            .left_border_interaction
+            .set_note(note, &model.key, drum_map);
     }
     //-- end: left control canvas
 
@@ -620,6 +1448,10 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
         .set(model.widget_ids.ball_control.bottom.channel, ui)
     {
         model.ball_model.bottom_border_interaction.midi_channel = (i + 1).try_into().unwrap();
+        model
+            .ball_model
+            .bottom_border_interaction
+            .relabel(&model.key, drum_map); // named as drum or note
     }
 
     // velocity
@@ -671,7 +1503,8 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
         + ui.wh_of(model.widget_ids.ball_control.bottom.length)
             .unwrap()[1]
         + stack_space;
-    for i in widget::DropDownList::new(&notenames, None)
+    let drum = model.ball_model.bottom_border_interaction.is_drum();
+    for i in widget::DropDownList::new(if drum { &drumnames } else { &notenames }, None)
         .mid_bottom_with_margin_on(
             model.widget_ids.ball_control.bottom.widget_canvas,
             widget_offset,
@@ -687,9 +1520,15 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
     // attach widget to UI
     {
         // process selection index
-        model.ball_model.bottom_border_interaction.note_display = notenames[i].to_string().clone();
-        model.ball_model.bottom_border_interaction.midi_note = (72 - i).try_into().unwrap();
-        // 72 = max MIDI note value
+        let note = if drum {
+            drumnotes[i]
+        } else {
+            mididata::MIDINOTES[i].1
+        };
+        model
+            .ball_model
+            .bottom_border_interaction
+            .set_note(note, &model.key, drum_map);
     }
     //-- end: bottom control canvas
 
@@ -723,6 +1562,10 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
         .set(model.widget_ids.ball_control.right.channel, ui)
     {
         model.ball_model.right_border_interaction.midi_channel = (i + 1).try_into().unwrap();
+        model
+            .ball_model
+            .right_border_interaction
+            .relabel(&model.key, drum_map); // named as drum or note
     }
 
     // velocity
@@ -774,7 +1617,8 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
         + ui.wh_of(model.widget_ids.ball_control.right.length)
             .unwrap()[1]
         + stack_space;
-    for i in widget::DropDownList::new(&notenames, None)
+    let drum = model.ball_model.right_border_interaction.is_drum();
+    for i in widget::DropDownList::new(if drum { &drumnames } else { &notenames }, None)
         .mid_bottom_with_margin_on(
             model.widget_ids.ball_control.right.widget_canvas,
             widget_offset,
@@ -790,9 +1634,15 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
     // attach widget to UI
     {
         // process selection index
-        model.ball_model.right_border_interaction.note_display = notenames[i].to_string().clone();
-        model.ball_model.right_border_interaction.midi_note = (72 - i).try_into().unwrap();
-        // 72 = max MIDI note value
+        let note = if drum {
+            drumnotes[i]
+        } else {
+            mididata::MIDINOTES[i].1
+        };
+        model
+            .ball_model
+            .right_border_interaction
+            .set_note(note, &model.key, drum_map);
     }
     //-- end: right control canvas
 
@@ -826,6 +1676,10 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
         .set(model.widget_ids.ball_control.top.channel, ui)
     {
         model.ball_model.top_border_interaction.midi_channel = (i + 1).try_into().unwrap();
+        model
+            .ball_model
+            .top_border_interaction
+            .relabel(&model.key, drum_map); // named as drum or note
     }
 
     // velocity
@@ -873,7 +1727,8 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
     widget_offset = widget_offset
         + ui.wh_of(model.widget_ids.ball_control.top.length).unwrap()[1]
         + stack_space;
-    for i in widget::DropDownList::new(&notenames, None)
+    let drum = model.ball_model.top_border_interaction.is_drum();
+    for i in widget::DropDownList::new(if drum { &drumnames } else { &notenames }, None)
         .mid_bottom_with_margin_on(
             model.widget_ids.ball_control.top.widget_canvas,
             widget_offset,
@@ -889,9 +1744,15 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
     // attach widget to UI
     {
         // process selection index
-        model.ball_model.top_border_interaction.note_display = notenames[i].to_string().clone();
-        model.ball_model.top_border_interaction.midi_note = (72 - i).try_into().unwrap();
-        // 72 = max MIDI note value
+        let note = if drum {
+            drumnotes[i]
+        } else {
+            mididata::MIDINOTES[i].1
+        };
+        model
+            .ball_model
+            .top_border_interaction
+            .set_note(note, &model.key, drum_map);
     }
     //-- end: top control canvas
 
@@ -938,217 +1799,1800 @@ fn update_handler(_app: &App, model: &mut Model, update: Update) {
         model.ball_model.ball.randomise_velocity();
     }
 
-    // current MIDI out ports list
-    let mut port_list = Vec::<String>::new();
-    let o = MidiOutput::new("bouncyquencer MIDI out"); // '?' operator can't be used
-    if let Err(e) = o {
-        eprintln!("MIDI out error: {}", e);
-        return;
-    }
-    let midi_out = o.unwrap();
-    for i in 0..midi_out.port_count() {
-        let pname = midi_out
-            .port_name(i)
-            .expect("error retrieving MIDI out port name");
-        port_list.push(pname);
-    }
-    // MIDI out port dropdown list widget -> last for "overlay effect" when selecting
-    for i in widget::DropDownList::new(&port_list, None)
-        .x(-280.0)
-        .y(230.0)
-        .border(1.0)
-        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
-        .scrollbar_next_to() // scrollbar on the right
-        .w(290.0) // absolute width
-        .h(25.0) // absolute height
-        .label(&model.midi.selected_output) // currently selected port / device
+    // GUI: arena control
+    widget::Canvas::new()
+        .x_relative_to(model.widget_ids.bounce_area, 0.0)
+        .y_relative_to(model.widget_ids.bounce_area, -245.0)
+        .w_h(200.0, 260.0)
+        .rgba(1.0, 0.0, 0.0, 0.0)
+        .border(0.0)
+        .title_bar("arena")
         .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
-        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
-        .set(model.widget_ids.midi_out_ports_list, ui)
-    // attach widget to UI
-    {
-        // process selection index emitted from the list
-        model.midi.selected_output = port_list[i].clone();
-        model.midi.out_port_number = i;
-        model.midi.out_usable = true;
-    }
-
-    // the ball
-    let border_r = model.bounce_area_width as f32 / 2.0 + model.bounce_area_center_x as f32;
-    let border_l = -model.bounce_area_width as f32 / 2.0 + model.bounce_area_center_x as f32;
-    let border_t = model.bounce_area_height as f32 / 2.0 + model.bounce_area_center_y as f32;
-    let border_b = -model.bounce_area_height as f32 / 2.0 + model.bounce_area_center_y as f32;
-
-    let mut v = model.ball_model.ball.get_velocity();
-    let mut pos = model.ball_model.ball.get_position();
-    let radius = model.ball_model.ball.get_radius();
-
-    // update the model
-    if pos.x + radius > border_r {
-        // hit right vertical border -> invert x-component
-        v.x = -1.0 * v.x;
-        // create MIDI messages & put into send queue
-        if "None" != model.ball_model.right_border_interaction.note_display {
-            let trig = update.since_start;
-            let dur = update.since_start
-                + Duration::from_millis(model.ball_model.right_border_interaction.length);
-            let (on, off) = TimedMidiMessage::create_on_off(
-                model.ball_model.right_border_interaction.midi_channel,
-                model.ball_model.right_border_interaction.midi_note,
-                model.ball_model.right_border_interaction.velocity,
-                trig,
-                dur,
-            );
-            model.midi.time_queue.push(on);
-            model.midi.time_queue.push(off);
-        }
-    }
-    if pos.x - radius < border_l {
-        // hit left vertical border -> invert x-component
-        v.x = -1.0 * v.x;
-        // create MIDI messages & put into send queue
-        if "None" != model.ball_model.left_border_interaction.note_display {
-            let trig = update.since_start;
-            let dur = update.since_start
-                + Duration::from_millis(model.ball_model.left_border_interaction.length);
-            let (on, off) = TimedMidiMessage::create_on_off(
-                model.ball_model.left_border_interaction.midi_channel,
-                model.ball_model.left_border_interaction.midi_note,
-                model.ball_model.left_border_interaction.velocity,
-                trig,
-                dur,
-            );
-            model.midi.time_queue.push(on);
-            model.midi.time_queue.push(off);
-        }
-    }
-    if pos.y + radius > border_t {
-        // hit top horizontal border -> invert y-component
-        v.y = -1.0 * v.y;
-        // create MIDI messages & put into send queue
-        if "None" != model.ball_model.top_border_interaction.note_display {
-            let trig = update.since_start;
-            let dur = update.since_start
-                + Duration::from_millis(model.ball_model.top_border_interaction.length);
-            let (on, off) = TimedMidiMessage::create_on_off(
-                model.ball_model.top_border_interaction.midi_channel,
-                model.ball_model.top_border_interaction.midi_note,
-                model.ball_model.top_border_interaction.velocity,
-                trig,
-                dur,
-            );
-            model.midi.time_queue.push(on);
-            model.midi.time_queue.push(off);
-        }
-    }
-    if pos.y - radius < border_b {
-        // hit bottom horizontal border -> invert y-component
-        v.y = -1.0 * v.y;
-        // create MIDI messages & put into send queue
-        if "None" != model.ball_model.bottom_border_interaction.note_display {
-            let trig = update.since_start;
-            let dur = update.since_start
-                + Duration::from_millis(model.ball_model.bottom_border_interaction.length);
-            let (on, off) = TimedMidiMessage::create_on_off(
-                model.ball_model.bottom_border_interaction.midi_channel,
-                model.ball_model.bottom_border_interaction.midi_note,
-                model.ball_model.bottom_border_interaction.velocity,
-                trig,
-                dur,
-            );
-            model.midi.time_queue.push(on);
-            model.midi.time_queue.push(off);
-        }
-    }
-
-    pos = pos + v; // calculate new position based on velocity
-    model.ball_model.ball.set_position(pos); // update position
-    model.ball_model.ball.set_velocity(v); // save (new) velocity vector
-    model.ball_model.velocity_x = v.x;
-    model.ball_model.velocity_y = v.y;
-
-    // --- end GUI code --- //
+        .set(model.widget_ids.arena_control.canvas, ui);
 
-    // --- begin MIDI code --- //
-    if let Some(tm) = model.midi.time_queue.peek() {
-        // see if it is (past) time to send message
-        if update.since_start >= tm.timestamp {
-            if model.midi.out_usable {
-                let msg = model.midi.time_queue.pop().unwrap();
-                model
-                    .midi
-                    .out_connection
-                    .send(&msg.to_bytes())
-                    .expect("could not send MIDI note on message");
-            }
-        }
+    let bpm_label = format!("{:.0} bpm", model.bpm);
+    for value in widget::Slider::new(model.bpm, 30.0, 300.0)
+        .mid_top_with_margin_on(model.widget_ids.arena_control.canvas, 25.0)
+        .w_h(200.0, 25.0)
+        .label(&bpm_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.arena_control.bpm, ui)
+    {
+        model.bpm = value.round();
     }
-    // --- end code --- //
 
-    // --- begin menue
-    // -- start GUI project name
-    for event in widget::TextBox::new(&model.project_name)
-        .x_relative_to(model.widget_ids.midi_out_ports_list, 270.0)
-        .y_relative_to(model.widget_ids.midi_out_ports_list, 0.0)
+    // rotation of the whole arena
+    let rotation_label = if model.arena.rotation.synced {
+        format!("rotation: {:.2} / beat", model.arena.rotation.rate)
+    } else {
+        format!("rotation: {:.2} / s", model.arena.rotation.rate)
+    };
+    for value in widget::Slider::new(model.arena.rotation.rate, -1.0, 1.0)
+        .down_from(model.widget_ids.arena_control.bpm, 4.0)
         .w_h(200.0, 25.0)
+        .label(&rotation_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
         .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
-        .border(0.0)
-        .text_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 1.0))
-        .left_justify()
-        .set(model.widget_ids.menue.project_name_textbox, ui)
+        .set(model.widget_ids.arena_control.rotation_rate, ui)
     {
-        if let nannou::ui::widget::text_box::Event::Update(txt) = event {
-            model.project_name = txt;
-        }
+        model.arena.rotation.rate = value;
     }
-    // -- end GUI project name
 
-    // -- start GUI save/load
-    for _ in widget::Button::new()
-        .x_relative_to(model.widget_ids.menue.project_name_textbox, 170.0)
-        .y_relative_to(model.widget_ids.menue.project_name_textbox, 0.0)
-        .w_h(75.0, 25.0)
-        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
-        .border(0.0)
-        .label("save")
+    for value in widget::Toggle::new(model.arena.rotation.synced)
+        .down_from(model.widget_ids.arena_control.rotation_rate, 4.0)
+        .w_h(200.0, 25.0)
+        .label("sync rotation to bpm")
         .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
-        .set(model.widget_ids.menue.save_button, ui)
+        .rgb(0.3, 0.3, 0.3)
+        .set(model.widget_ids.arena_control.rotation_synced, ui)
     {
-        model.do_save_state = true;
+        model.arena.rotation.synced = value;
     }
 
-    for _ in widget::Button::new()
-        .x_relative_to(model.widget_ids.menue.save_button, 100.0)
-        .y_relative_to(model.widget_ids.menue.save_button, 0.0)
-        .w_h(75.0, 25.0)
+    // oscillation of a single wall
+    let wall_names: Vec<&str> = Wall::ALL.iter().map(|w| w.name()).collect();
+    let wall_index = model.selected_wall.index();
+    let amplitude_label = format!(
+        "{} wall: {:.0} px",
+        model.selected_wall.name(),
+        model.arena.wall_motions[wall_index].amplitude
+    );
+    for value in widget::Slider::new(model.arena.wall_motions[wall_index].amplitude, -50.0, 50.0)
+        .down_from(model.widget_ids.arena_control.rotation_synced, 37.0)
+        .w_h(200.0, 25.0)
+        .label(&amplitude_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
         .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
-        .border(0.0)
-        .label("load")
-        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
-        .set(model.widget_ids.menue.load_button, ui)
+        .set(model.widget_ids.arena_control.wall_amplitude, ui)
     {
-        model.do_load_state = true; // run model state in next run before doing GUI work
+        model.arena.wall_motions[wall_index].amplitude = value;
     }
-    // -- end GUI save/load
 
-    // -- start GUI about
-    for _ in widget::Button::new()
-        .x_relative_to(model.widget_ids.menue.load_button, 100.0)
-        .y_relative_to(model.widget_ids.menue.load_button, 0.0)
-        .w_h(75.0, 25.0)
+    let wall_rate_label = if model.arena.wall_motions[wall_index].synced {
+        format!(
+            "speed: {:.2} / beat",
+            model.arena.wall_motions[wall_index].rate
+        )
+    } else {
+        format!(
+            "speed: {:.2} / s",
+            model.arena.wall_motions[wall_index].rate
+        )
+    };
+    for value in widget::Slider::new(model.arena.wall_motions[wall_index].rate, 0.0, 2.0)
+        .down_from(model.widget_ids.arena_control.wall_amplitude, 4.0)
+        .w_h(200.0, 25.0)
+        .label(&wall_rate_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
         .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
-        .border(0.0)
-        .label("about")
-        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
-        .set(model.widget_ids.menue.about_button, ui)
+        .set(model.widget_ids.arena_control.wall_rate, ui)
     {
-        // toggle display about text
-        model.should_display_about = !model.should_display_about;
-        model.should_display_mit_license = false;
+        model.arena.wall_motions[wall_index].rate = value;
     }
 
-    if model.should_display_about {
-        // the canvas to group widgets & control background etc.
+    for value in widget::Toggle::new(model.arena.wall_motions[wall_index].synced)
+        .down_from(model.widget_ids.arena_control.wall_rate, 4.0)
+        .w_h(200.0, 25.0)
+        .label("sync wall to bpm")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .rgb(0.3, 0.3, 0.3)
+        .set(model.widget_ids.arena_control.wall_synced, ui)
+    {
+        model.arena.wall_motions[wall_index].synced = value;
+    }
+
+    let restitution_label = format!("restitution: {:.2}", model.arena.restitution[wall_index]);
+    for value in widget::Slider::new(model.arena.restitution[wall_index], 0.0, 1.5)
+        .down_from(model.widget_ids.arena_control.wall_synced, 4.0)
+        .w_h(200.0, 25.0)
+        .label(&restitution_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.arena_control.wall_restitution, ui)
+    {
+        model.arena.restitution[wall_index] = value;
+    }
+
+    // GUI: physics control
+    widget::Canvas::new()
+        .x_relative_to(model.widget_ids.arena_control.canvas, -220.0)
+        .y_relative_to(model.widget_ids.arena_control.canvas, 0.0)
+        .wh_of(model.widget_ids.arena_control.canvas)
+        .rgba(1.0, 0.0, 0.0, 0.0)
+        .border(0.0)
+        .title_bar("physics")
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .set(model.widget_ids.physics_control.canvas, ui);
+
+    let gravity_label = format!("gravity: {:.2}", model.physics.gravity);
+    for value in widget::Slider::new(model.physics.gravity, 0.0, 1.0)
+        .mid_top_with_margin_on(model.widget_ids.physics_control.canvas, 25.0)
+        .w_h(200.0, 25.0)
+        .label(&gravity_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.physics_control.gravity, ui)
+    {
+        model.physics.gravity = value;
+    }
+
+    let gravity_angle_label = format!("direction: {:.0}°", model.physics.gravity_angle);
+    for value in widget::Slider::new(model.physics.gravity_angle, 0.0, 360.0)
+        .down_from(model.widget_ids.physics_control.gravity, 4.0)
+        .w_h(200.0, 25.0)
+        .label(&gravity_angle_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.physics_control.gravity_angle, ui)
+    {
+        model.physics.gravity_angle = value.round();
+    }
+
+    let drag_label = format!("drag: {:.3}", model.physics.drag);
+    for value in widget::Slider::new(model.physics.drag, 0.0, 0.05)
+        .down_from(model.widget_ids.physics_control.gravity_angle, 4.0)
+        .w_h(200.0, 25.0)
+        .label(&drag_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.physics_control.drag, ui)
+    {
+        model.physics.drag = value;
+    }
+
+    for value in widget::Toggle::new(model.physics.rekick)
+        .down_from(model.widget_ids.physics_control.drag, 4.0)
+        .w_h(200.0, 25.0)
+        .label("re-kick slow ball")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .rgb(0.3, 0.3, 0.3)
+        .set(model.widget_ids.physics_control.rekick, ui)
+    {
+        model.physics.rekick = value;
+    }
+
+    let rekick_threshold_label = format!("when slower: {:.2}", model.physics.rekick_threshold);
+    for value in widget::Slider::new(model.physics.rekick_threshold, 0.0, 5.0)
+        .down_from(model.widget_ids.physics_control.rekick, 4.0)
+        .w_h(200.0, 25.0)
+        .label(&rekick_threshold_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.physics_control.rekick_threshold, ui)
+    {
+        model.physics.rekick_threshold = value;
+    }
+
+    let rekick_speed_label = format!("kick to: {:.2}", model.physics.rekick_speed);
+    for value in widget::Slider::new(model.physics.rekick_speed, 0.0, 15.0)
+        .down_from(model.widget_ids.physics_control.rekick_threshold, 4.0)
+        .w_h(200.0, 25.0)
+        .label(&rekick_speed_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.physics_control.rekick_speed, ui)
+    {
+        model.physics.rekick_speed = value;
+    }
+
+    // GUI: attractor control
+    widget::Canvas::new()
+        .x_relative_to(model.widget_ids.physics_control.canvas, -220.0)
+        .y_relative_to(model.widget_ids.physics_control.canvas, 0.0)
+        .wh_of(model.widget_ids.physics_control.canvas)
+        .rgba(1.0, 0.0, 0.0, 0.0)
+        .border(0.0)
+        .title_bar("attractors")
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .set(model.widget_ids.attractor_control.canvas, ui);
+
+    for _click in widget::Button::new()
+        .top_left_with_margins_on(model.widget_ids.attractor_control.canvas, 25.0, 0.0)
+        .w_h(98.0, 25.0)
+        .label("add")
+        .rgb(0.3, 0.3, 0.3)
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .border(0.0)
+        .set(model.widget_ids.attractor_control.add_button, ui)
+    {
+        model.attractors.push(Attractor::new(model.arena.center));
+        model.selected_attractor = model.attractors.len() - 1;
+    }
+
+    for _click in widget::Button::new()
+        .right_from(model.widget_ids.attractor_control.add_button, 4.0)
+        .w_h(98.0, 25.0)
+        .label("remove")
+        .rgb(0.3, 0.3, 0.3)
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .border(0.0)
+        .set(model.widget_ids.attractor_control.remove_button, ui)
+    {
+        if model.selected_attractor < model.attractors.len() {
+            model.attractors.remove(model.selected_attractor);
+            model.selected_attractor = model.selected_attractor.saturating_sub(1);
+        }
+    }
+
+    if model.selected_attractor < model.attractors.len() {
+        let i_attr = model.selected_attractor;
+        let half_w = model.arena.width / 2.0;
+        let half_h = model.arena.height / 2.0;
+        let rel = model.attractors[i_attr].position - model.arena.center;
+
+        let x_label = format!("x: {:.0}", rel.x);
+        for value in widget::Slider::new(rel.x, -half_w, half_w)
+            .down_from(model.widget_ids.attractor_control.add_button, 33.0)
+            .w_h(200.0, 25.0)
+            .label(&x_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.attractor_control.x, ui)
+        {
+            model.attractors[i_attr].position.x = model.arena.center.x + value;
+        }
+
+        let y_label = format!("y: {:.0}", rel.y);
+        for value in widget::Slider::new(rel.y, -half_h, half_h)
+            .down_from(model.widget_ids.attractor_control.x, 4.0)
+            .w_h(200.0, 25.0)
+            .label(&y_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.attractor_control.y, ui)
+        {
+            model.attractors[i_attr].position.y = model.arena.center.y + value;
+        }
+
+        let strength_label = format!("strength: {:.2}", model.attractors[i_attr].strength);
+        for value in widget::Slider::new(model.attractors[i_attr].strength, -1.0, 1.0)
+            .down_from(model.widget_ids.attractor_control.y, 4.0)
+            .w_h(200.0, 25.0)
+            .label(&strength_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.attractor_control.strength, ui)
+        {
+            model.attractors[i_attr].strength = value;
+        }
+
+        let falloff_label = format!("falloff: {:.1}", model.attractors[i_attr].falloff);
+        for value in widget::Slider::new(model.attractors[i_attr].falloff, 0.0, 3.0)
+            .down_from(model.widget_ids.attractor_control.strength, 4.0)
+            .w_h(200.0, 25.0)
+            .label(&falloff_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.attractor_control.falloff, ui)
+        {
+            model.attractors[i_attr].falloff = value;
+        }
+
+        let radius_label = format!(
+            "trigger radius: {:.0}",
+            model.attractors[i_attr].trigger_radius
+        );
+        for value in widget::Slider::new(model.attractors[i_attr].trigger_radius, 0.0, 100.0)
+            .down_from(model.widget_ids.attractor_control.falloff, 4.0)
+            .w_h(200.0, 25.0)
+            .label(&radius_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.attractor_control.trigger_radius, ui)
+        {
+            model.attractors[i_attr].trigger_radius = value;
+        }
+
+        let note_label = mididata::MIDINOTES
+            .iter()
+            .find(|tuple| {
+                let attractor = &model.attractors[i_attr];
+                tuple.1
+                    == model
+                        .key
+                        .transpose_from(attractor.note_root, attractor.midi_note)
+            })
+            .map(|tuple| tuple.0)
+            .unwrap_or("None");
+        for i in widget::DropDownList::new(&notenames, None)
+            .down_from(model.widget_ids.attractor_control.trigger_radius, 4.0)
+            .w_h(200.0, 25.0)
+            .border(1.0)
+            .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+            .scrollbar_next_to() // scrollbar on the right
+            .label(note_label) // currently selected MIDI note
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+            .set(model.widget_ids.attractor_control.note, ui)
+        {
+            model.attractors[i_attr].midi_note = mididata::MIDINOTES[i].1;
+            model.attractors[i_attr].note_root = model.key.root;
+        }
+    }
+
+    // attractor selection -> after its controls for "overlay effect" when selecting
+    let attractor_names: Vec<String> = (1..=model.attractors.len())
+        .map(|n| format!("attractor {}", n))
+        .collect();
+    let selected_attractor = if model.attractors.is_empty() {
+        None
+    } else {
+        Some(model.selected_attractor)
+    };
+    for i in widget::DropDownList::new(&attractor_names, selected_attractor)
+        .down_from(model.widget_ids.attractor_control.add_button, 4.0)
+        .align_left_of(model.widget_ids.attractor_control.add_button)
+        .w_h(200.0, 25.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .label("no attractor")
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.attractor_control.select, ui)
+    {
+        model.selected_attractor = i;
+    }
+
+    // GUI: wall behaviour control (from bottom up for overlay effect)
+    widget::Canvas::new()
+        .x_relative_to(model.widget_ids.attractor_control.canvas, -220.0)
+        .y_relative_to(model.widget_ids.attractor_control.canvas, 0.0)
+        .wh_of(model.widget_ids.attractor_control.canvas)
+        .rgba(1.0, 0.0, 0.0, 0.0)
+        .border(0.0)
+        .title_bar("walls")
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .set(model.widget_ids.wall_control.canvas, ui);
+
+    // the sound of the selected wall: program & controllers sent with each note
+    let program = model.ball_model.interaction(model.selected_wall).program;
+    let program_label = if program < 128 {
+        format!("program: {}", program)
+    } else {
+        "program: none".to_string()
+    };
+    for value in widget::Slider::new(program as f32, 0.0, 128.0)
+        .top_left_with_margins_on(model.widget_ids.wall_control.canvas, 199.0, 0.0)
+        .w_h(98.0, 25.0)
+        .label(&program_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.wall_control.program, ui)
+    {
+        model
+            .ball_model
+            .interaction_mut(model.selected_wall)
+            .program = value.round() as u8;
+    }
+
+    for event in widget::TextBox::new(&model.controllers)
+        .top_right_with_margins_on(model.widget_ids.wall_control.canvas, 199.0, 0.0)
+        .w_h(98.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .text_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 1.0))
+        .left_justify()
+        .set(model.widget_ids.wall_control.controllers, ui)
+    {
+        match event {
+            nannou::ui::widget::text_box::Event::Update(txt) => model.controllers = txt,
+            nannou::ui::widget::text_box::Event::Enter => {
+                match midi::parse_controllers(&model.controllers) {
+                    Ok(controllers) => {
+                        model
+                            .ball_model
+                            .interaction_mut(model.selected_wall)
+                            .controllers = controllers
+                    }
+                    Err(e) => eprintln!("invalid controllers: {}", e),
+                }
+            }
+        }
+    }
+
+    // the selected wall as a keyboard: the pitch depends on where it is hit
+    let zones = model.ball_model.interaction(model.selected_wall).zones;
+    if zones != Zones::Off {
+        let count_label = zones.name();
+        for value in widget::Slider::new(zones.count() as f32, 2.0, zone::MAX_ZONES as f32)
+            .top_right_with_margins_on(model.widget_ids.wall_control.canvas, 170.0, 0.0)
+            .w_h(98.0, 25.0)
+            .label(&count_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.wall_control.zone_count, ui)
+        {
+            model.ball_model.interaction_mut(model.selected_wall).zones =
+                Zones::new(zones.mode(), value.round() as u8);
+        }
+    }
+
+    let pitch_label = format!("pitch: {}", Zones::MODES[zones.mode()]);
+    for i in widget::DropDownList::new(&Zones::MODES, Some(zones.mode()))
+        .top_left_with_margins_on(model.widget_ids.wall_control.canvas, 170.0, 0.0)
+        .w_h(98.0, 25.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .label(&pitch_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.wall_control.pitch_mode, ui)
+    {
+        // keep the number when switching between zones and glide
+        let count = if zones == Zones::Off {
+            8
+        } else {
+            zones.count()
+        };
+        model.ball_model.interaction_mut(model.selected_wall).zones = Zones::new(i, count);
+    }
+
+    let teleport_label = format!(
+        "portal note: {}",
+        model.ball_model.teleport_interaction.note_display
+    );
+    for i in widget::DropDownList::new(&notenames, None)
+        .mid_top_with_margin_on(model.widget_ids.wall_control.canvas, 141.0)
+        .w_h(200.0, 25.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .label(&teleport_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.wall_control.teleport_note, ui)
+    {
+        model.ball_model.teleport_interaction.set_note(
+            mididata::MIDINOTES[i].1,
+            &model.key,
+            drum_map,
+        );
+    }
+
+    let wrap_label = format!(
+        "wrap note: {}",
+        model.ball_model.wrap_interaction.note_display
+    );
+    for i in widget::DropDownList::new(&notenames, None)
+        .mid_top_with_margin_on(model.widget_ids.wall_control.canvas, 112.0)
+        .w_h(200.0, 25.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .label(&wrap_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.wall_control.wrap_note, ui)
+    {
+        model
+            .ball_model
+            .wrap_interaction
+            .set_note(mididata::MIDINOTES[i].1, &model.key, drum_map);
+    }
+
+    if model.arena.behaviours[wall_index] == WallBehaviour::Portal {
+        let portal_rotation_label = format!(
+            "exit rotation: {:.0}°",
+            model.arena.portals[wall_index].rotation
+        );
+        for value in widget::Slider::new(model.arena.portals[wall_index].rotation, -90.0, 90.0)
+            .mid_top_with_margin_on(model.widget_ids.wall_control.canvas, 83.0)
+            .w_h(200.0, 25.0)
+            .label(&portal_rotation_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.wall_control.portal_rotation, ui)
+        {
+            model.arena.portals[wall_index].rotation = value.round();
+        }
+
+        let portal_target_label = format!(
+            "exit through: {}",
+            model.arena.portals[wall_index].target.name()
+        );
+        for i in widget::DropDownList::new(&wall_names, None)
+            .mid_top_with_margin_on(model.widget_ids.wall_control.canvas, 54.0)
+            .w_h(200.0, 25.0)
+            .border(1.0)
+            .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+            .scrollbar_next_to() // scrollbar on the right
+            .label(&portal_target_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+            .set(model.widget_ids.wall_control.portal_target, ui)
+        {
+            model.arena.portals[wall_index].target = Wall::ALL[i];
+        }
+    }
+
+    let behaviour_names: Vec<&str> = WallBehaviour::ALL.iter().map(|b| b.name()).collect();
+    let behaviour_label = format!(
+        "{} wall: {}",
+        model.selected_wall.name(),
+        model.arena.behaviours[wall_index].name()
+    );
+    for i in widget::DropDownList::new(&behaviour_names, None)
+        .mid_top_with_margin_on(model.widget_ids.wall_control.canvas, 25.0)
+        .w_h(200.0, 25.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .label(&behaviour_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.wall_control.behaviour, ui)
+    {
+        model.arena.behaviours[wall_index] = WallBehaviour::ALL[i];
+    }
+
+    // GUI: rhythm preview (the hits are drawn in the view)
+    widget::Canvas::new()
+        .x_relative_to(model.widget_ids.bounce_area, 270.0)
+        .y_relative_to(model.widget_ids.bounce_area, 40.0)
+        .w_h(260.0, 240.0)
+        .rgba(1.0, 0.0, 0.0, 0.0)
+        .border(0.0)
+        .title_bar("rhythm")
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .set(model.widget_ids.rhythm_control.canvas, ui);
+
+    let hits_label = format!("hits: {}", model.preview_hits);
+    for value in widget::Slider::new(model.preview_hits as f32, 1.0, 64.0)
+        .top_left_with_margins_on(model.widget_ids.rhythm_control.canvas, 25.0, 0.0)
+        .w_h(128.0, 25.0)
+        .label(&hits_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.rhythm_control.hits, ui)
+    {
+        model.preview_hits = value.round() as usize;
+    }
+
+    let beats_label = format!("beats: {:.0}", model.preview_beats);
+    for value in widget::Slider::new(model.preview_beats, 1.0, 16.0)
+        .right_from(model.widget_ids.rhythm_control.hits, 4.0)
+        .w_h(128.0, 25.0)
+        .label(&beats_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.rhythm_control.beats, ui)
+    {
+        model.preview_beats = value.round();
+    }
+
+    // velocity solver: the inverse of the velocity pad
+    let solve_x_label = format!("l/r: {}", model.solver_beats_x);
+    for value in widget::Slider::new(model.solver_beats_x, 0.0, 16.0)
+        .down_from(model.widget_ids.rhythm_control.hits, 160.0)
+        .w_h(85.0, 25.0)
+        .label(&solve_x_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.rhythm_control.solve_x, ui)
+    {
+        model.solver_beats_x = (value * 2.0).round() / 2.0; // half beats
+    }
+
+    let solve_y_label = format!("t/b: {}", model.solver_beats_y);
+    for value in widget::Slider::new(model.solver_beats_y, 0.0, 16.0)
+        .right_from(model.widget_ids.rhythm_control.solve_x, 4.0)
+        .w_h(85.0, 25.0)
+        .label(&solve_y_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.rhythm_control.solve_y, ui)
+    {
+        model.solver_beats_y = (value * 2.0).round() / 2.0;
+    }
+
+    for _click in widget::Button::new()
+        .right_from(model.widget_ids.rhythm_control.solve_y, 4.0)
+        .w_h(82.0, 25.0)
+        .label("solve")
+        .rgb(0.3, 0.3, 0.3)
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .border(0.0)
+        .set(model.widget_ids.rhythm_control.solve_button, ui)
+    {
+        let v = rhythm::solve(
+            &model.arena,
+            &model.ball_model.ball,
+            model.solver_beats_x,
+            model.solver_beats_y,
+            model.bpm,
+        );
+        model.ball_model.ball.set_velocity(v);
+        model.ball_model.velocity_x = v.x;
+        model.ball_model.velocity_y = v.y;
+    }
+
+    // GUI: key (above the rhythm preview), a changed key moves all notes
+    for event in widget::TextBox::new(&model.custom_scale)
+        .x_relative_to(model.widget_ids.rhythm_control.canvas, 100.0)
+        .y_relative_to(model.widget_ids.rhythm_control.canvas, 142.0)
+        .w_h(60.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .text_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 1.0))
+        .left_justify()
+        .set(model.widget_ids.key_control.custom, ui)
+    {
+        match event {
+            nannou::ui::widget::text_box::Event::Update(txt) => model.custom_scale = txt,
+            nannou::ui::widget::text_box::Event::Enter => match Scale::parse(&model.custom_scale) {
+                Ok(scale) => {
+                    let key = Key {
+                        root: model.key.root,
+                        scale,
+                    };
+                    change_key(&mut model.ball_model, &mut model.key, key, drum_map);
+                }
+                Err(e) => eprintln!("invalid scale {}: {}", model.custom_scale, e),
+            },
+        }
+    }
+
+    let scales = scale::presets();
+    let mut scale_names: Vec<String> = scales.iter().map(|s| s.name.clone()).collect();
+    scale_names.push("custom".to_string());
+    for i in widget::DropDownList::new(&scale_names, None)
+        .left_from(model.widget_ids.key_control.custom, 4.0)
+        .w_h(131.0, 25.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .label(&model.key.scale.name)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.key_control.scale, ui)
+    {
+        let scale = match scales.get(i) {
+            Some(scale) => scale.clone(),
+            None => match Scale::parse(&model.custom_scale) {
+                Ok(scale) => scale,
+                Err(e) => {
+                    eprintln!("invalid scale {}: {}", model.custom_scale, e);
+                    continue;
+                }
+            },
+        };
+        let key = Key {
+            root: model.key.root,
+            scale,
+        };
+        change_key(&mut model.ball_model, &mut model.key, key, drum_map);
+    }
+
+    for i in widget::DropDownList::new(&scale::ROOTS, Some(model.key.root as usize))
+        .left_from(model.widget_ids.key_control.scale, 4.0)
+        .w_h(57.0, 25.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.key_control.root, ui)
+    {
+        let key = Key {
+            root: i as u8,
+            scale: model.key.scale.clone(),
+        };
+        change_key(&mut model.ball_model, &mut model.key, key, drum_map);
+    }
+
+    // names of the drums on the drum channel (General MIDI or own maps)
+    let drum_map_names: Vec<&str> = model.drum_maps.iter().map(|m| m.name.as_str()).collect();
+    let drum_map_label = format!("drums (channel {}): {}", drums::CHANNEL, drum_map.name);
+    for i in widget::DropDownList::new(&drum_map_names, Some(model.drum_map))
+        .x_relative_to(model.widget_ids.rhythm_control.canvas, 2.0)
+        .y_relative_to(model.widget_ids.rhythm_control.canvas, 171.0)
+        .w_h(256.0, 25.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .label(&drum_map_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.key_control.drums, ui)
+    {
+        model.drum_map = i;
+        model
+            .ball_model
+            .relabel_notes(&model.key, &model.drum_maps[i]);
+    }
+
+    // reference pitch the tuning (and thus every frequency) is based on
+    let reference_label = format!(
+        "reference: {} = {:.1} Hz",
+        mididata::note_name(model.tuning.keyboard.reference_note),
+        model.tuning.keyboard.reference_frequency
+    );
+    for value in widget::Slider::new(model.tuning.keyboard.reference_frequency, 400.0, 480.0)
+        .x_relative_to(model.widget_ids.rhythm_control.canvas, 2.0)
+        .y_relative_to(model.widget_ids.rhythm_control.canvas, 258.0)
+        .w_h(256.0, 25.0)
+        .label(&reference_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.key_control.reference, ui)
+    {
+        model.tuning.set_reference((value * 10.0).round() / 10.0);
+    }
+
+    // tuning: Scala files (e.g. "just.scl" or "just.scl just.kbm", none = 12-TET)
+    for event in widget::TextBox::new(&model.tuning_files)
+        .x_relative_to(model.widget_ids.rhythm_control.canvas, 2.0)
+        .y_relative_to(model.widget_ids.rhythm_control.canvas, 200.0)
+        .w_h(256.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .text_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 1.0))
+        .left_justify()
+        .set(model.widget_ids.key_control.tuning_files, ui)
+    {
+        match event {
+            nannou::ui::widget::text_box::Event::Update(txt) => model.tuning_files = txt,
+            nannou::ui::widget::text_box::Event::Enter => match Tuning::load(&model.tuning_files) {
+                Ok(mut tuning) => {
+                    // without a keyboard mapping the reference pitch stays
+                    if tuning.keyboard == tuning::Kbm::standard() {
+                        tuning.set_reference(model.tuning.keyboard.reference_frequency);
+                    }
+                    model.tuning = tuning;
+                    reset_tunings(&mut model.midi);
+                }
+                Err(e) => eprintln!("tuning error: {}", e),
+            },
+        }
+    }
+
+    let channels_label = format!("bend channels: {}", model.midi.retune.channels);
+    for value in widget::Slider::new(model.midi.retune.channels as f32, 1.0, 16.0)
+        .x_relative_to(model.widget_ids.rhythm_control.canvas, 67.0)
+        .y_relative_to(model.widget_ids.rhythm_control.canvas, 229.0)
+        .w_h(126.0, 25.0)
+        .label(&channels_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .set(model.widget_ids.key_control.tuning_channels, ui)
+    {
+        model.midi.retune.channels = value.round() as u8;
+    }
+
+    let tuning_output_names: Vec<&str> = TuningOutput::ALL.iter().map(|o| o.name()).collect();
+    let tuning_label = format!("{}: {}", model.tuning.name, model.midi.retune.output.name());
+    for i in widget::DropDownList::new(&tuning_output_names, None)
+        .x_relative_to(model.widget_ids.rhythm_control.canvas, -63.0)
+        .y_relative_to(model.widget_ids.rhythm_control.canvas, 229.0)
+        .w_h(126.0, 25.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .label(&tuning_label)
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.key_control.tuning_output, ui)
+    {
+        model.midi.retune.output = TuningOutput::ALL[i];
+        reset_tunings(&mut model.midi);
+    }
+
+    // GUI: output control
+    widget::Canvas::new()
+        .x_relative_to(model.widget_ids.wall_control.canvas, -220.0)
+        .y_relative_to(model.widget_ids.wall_control.canvas, 0.0)
+        .wh_of(model.widget_ids.wall_control.canvas)
+        .rgba(1.0, 0.0, 0.0, 0.0)
+        .border(0.0)
+        .title_bar("outputs")
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .set(model.widget_ids.output_control.canvas, ui);
+
+    let backend = model.selected_backend;
+    let route = &mut model.routes[backend.index()];
+    for value in widget::Toggle::new(route.active)
+        .mid_top_with_margin_on(model.widget_ids.output_control.canvas, 54.0)
+        .w_h(200.0, 25.0)
+        .label("active")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .rgb(0.3, 0.3, 0.3)
+        .set(model.widget_ids.output_control.active, ui)
+    {
+        route.active = value;
+        if value && backend == Backend::Pd && model.pd.ball.is_none() {
+            model.pd.ball = connect_pd(&model.pd.target);
+        }
+        if value && backend == Backend::Osc && model.osc.ball.is_none() {
+            model.osc.ball = connect_osc(&model.osc.target, model.osc.bundles);
+        }
+    }
+
+    // where to send to (press enter to connect)
+    if backend == Backend::Pd || backend == Backend::Osc {
+        let target = if backend == Backend::Pd {
+            model.pd.target.clone()
+        } else {
+            model.osc.target.clone()
+        };
+        for event in widget::TextBox::new(&target)
+            .mid_top_with_margin_on(model.widget_ids.output_control.canvas, 83.0)
+            .w_h(200.0, 25.0)
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .border(0.0)
+            .text_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 1.0))
+            .left_justify()
+            .set(model.widget_ids.output_control.target, ui)
+        {
+            match (event, backend) {
+                (nannou::ui::widget::text_box::Event::Update(txt), Backend::Pd) => {
+                    model.pd.target = txt
+                }
+                (nannou::ui::widget::text_box::Event::Update(txt), _) => model.osc.target = txt,
+                (nannou::ui::widget::text_box::Event::Enter, Backend::Pd) => {
+                    model.pd.ball = connect_pd(&model.pd.target);
+                }
+                (nannou::ui::widget::text_box::Event::Enter, _) => {
+                    model.osc.ball = connect_osc(&model.osc.target, model.osc.bundles);
+                }
+            }
+        }
+    }
+
+    if backend == Backend::Osc {
+        for value in widget::Toggle::new(model.osc.bundles)
+            .mid_top_with_margin_on(model.widget_ids.output_control.canvas, 112.0)
+            .w_h(200.0, 25.0)
+            .label("bundles with timetags")
+            .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+            .rgb(0.3, 0.3, 0.3)
+            .set(model.widget_ids.output_control.bundles, ui)
+        {
+            model.osc.bundles = value;
+            if let Some(osc_ball) = model.osc.ball.as_mut() {
+                osc_ball.set_bundled(value);
+            }
+        }
+    }
+
+    // routing per wall, for the attractors and per ball
+    for wall in Wall::ALL.iter() {
+        let i = wall.index();
+        let toggle = widget::Toggle::new(route.walls[i])
+            .w_h(47.0, 25.0)
+            .label(wall.name())
+            .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+            .rgb(0.3, 0.3, 0.3);
+        let toggle = if i == 0 {
+            toggle.top_left_with_margins_on(model.widget_ids.output_control.canvas, 141.0, 0.0)
+        } else {
+            toggle.right_from(model.widget_ids.output_control.walls[i - 1], 4.0)
+        };
+        for value in toggle.set(model.widget_ids.output_control.walls[i], ui) {
+            route.walls[i] = value;
+        }
+    }
+
+    for value in widget::Toggle::new(route.attractors)
+        .mid_top_with_margin_on(model.widget_ids.output_control.canvas, 170.0)
+        .w_h(200.0, 25.0)
+        .label("attractors")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .rgb(0.3, 0.3, 0.3)
+        .set(model.widget_ids.output_control.attractors, ui)
+    {
+        route.attractors = value;
+    }
+
+    let filter_names: Vec<&str> = BallFilter::ALL.iter().map(|f| f.name()).collect();
+    let filter_index = BallFilter::ALL.iter().position(|f| *f == route.balls);
+    for i in widget::DropDownList::new(&filter_names, filter_index)
+        .mid_top_with_margin_on(model.widget_ids.output_control.canvas, 199.0)
+        .w_h(200.0, 25.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.output_control.balls, ui)
+    {
+        route.balls = BallFilter::ALL[i];
+    }
+
+    // MPE, additional MIDI out ports & the one of each event source
+    // (from bottom up for overlay effect)
+    if backend == Backend::Midi {
+        for value in widget::Toggle::new(model.midi.mpe.active)
+            .top_left_with_margins_on(model.widget_ids.output_control.canvas, 228.0, 0.0)
+            .w_h(98.0, 25.0)
+            .label("MPE")
+            .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+            .rgb(0.3, 0.3, 0.3)
+            .set(model.widget_ids.output_control.mpe, ui)
+        {
+            model.midi.mpe.active = value;
+            if value {
+                // announce the zone on every open port
+                release_all(&mut model.midi);
+                let mut ports = vec![String::new()];
+                ports.extend(model.midi.outputs.iter().map(|o| o.name.clone()));
+                for port in ports.iter() {
+                    for bytes in model.midi.mpe.configuration().iter() {
+                        send_bytes(&mut model.midi, port, bytes);
+                    }
+                }
+            }
+        }
+        let expression_names: Vec<&str> = Expression::ALL.iter().map(|e| e.name()).collect();
+        let expression_index = Expression::ALL
+            .iter()
+            .position(|e| *e == model.midi.mpe.expression);
+        for i in widget::DropDownList::new(&expression_names, expression_index)
+            .right_from(model.widget_ids.output_control.mpe, 4.0)
+            .w_h(98.0, 25.0)
+            .border(1.0)
+            .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+            .scrollbar_next_to() // scrollbar on the right
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+            .set(model.widget_ids.output_control.mpe_expression, ui)
+        {
+            model.midi.mpe.expression = Expression::ALL[i];
+        }
+
+        let source = model.port_source;
+        for i in widget::DropDownList::new(&PORT_SOURCES, Some(source))
+            .top_left_with_margins_on(model.widget_ids.output_control.canvas, 112.0, 0.0)
+            .w_h(98.0, 25.0)
+            .border(1.0)
+            .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+            .scrollbar_next_to() // scrollbar on the right
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+            .set(model.widget_ids.output_control.port_source, ui)
+        {
+            model.port_source = i;
+        }
+
+        let mut source_ports = vec!["default output".to_string()];
+        source_ports.extend(model.midi.outputs.iter().map(|o| o.name.clone()));
+        let port = source_port(&mut model.ball_model, &mut model.attractor_port, source);
+        let current = if port.is_empty() {
+            "default output".to_string()
+        } else {
+            port.clone()
+        };
+        for i in widget::DropDownList::new(&source_ports, None)
+            .right_from(model.widget_ids.output_control.port_source, 4.0)
+            .w_h(98.0, 25.0)
+            .border(1.0)
+            .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+            .scrollbar_next_to() // scrollbar on the right
+            .label(&current)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+            .set(model.widget_ids.output_control.source_port, ui)
+        {
+            *source_port(&mut model.ball_model, &mut model.attractor_port, source) = if i == 0 {
+                String::new()
+            } else {
+                source_ports[i].clone()
+            };
+        }
+
+        // selecting a port opens it, selecting an open one closes it
+        let names = output_port_names();
+        let entries: Vec<String> = names
+            .iter()
+            .map(|name| {
+                if model.midi.outputs.iter().any(|o| &o.name == name) {
+                    format!("close {}", name)
+                } else {
+                    format!("open {}", name)
+                }
+            })
+            .collect();
+        for i in widget::DropDownList::new(&entries, None)
+            .mid_top_with_margin_on(model.widget_ids.output_control.canvas, 83.0)
+            .w_h(200.0, 25.0)
+            .border(1.0)
+            .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+            .scrollbar_next_to() // scrollbar on the right
+            .label(&format!("{} more ports open", model.midi.outputs.len()))
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+            .set(model.widget_ids.output_control.ports, ui)
+        {
+            if model.midi.outputs.iter().any(|o| o.name == names[i]) {
+                close_additional_output(
+                    &mut model.midi,
+                    &mut model.ball_model,
+                    &mut model.attractor_port,
+                    &names[i],
+                );
+            } else {
+                open_additional_output(&mut model.midi, &names[i]);
+            }
+        }
+    }
+
+    // backend selection -> last for "overlay effect" when selecting
+    let backend_names: Vec<&str> = Backend::ALL.iter().map(|b| b.name()).collect();
+    for i in widget::DropDownList::new(&backend_names, Some(backend.index()))
+        .mid_top_with_margin_on(model.widget_ids.output_control.canvas, 25.0)
+        .w_h(200.0, 25.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.output_control.select, ui)
+    {
+        model.selected_backend = Backend::ALL[i];
+    }
+
+    // GUI: emitter control
+    widget::Canvas::new()
+        .x_relative_to(model.widget_ids.arena_control.canvas, 220.0)
+        .y_relative_to(model.widget_ids.arena_control.canvas, -40.0)
+        .w_h(200.0, 340.0)
+        .rgba(1.0, 0.0, 0.0, 0.0)
+        .border(0.0)
+        .title_bar("emitters")
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .set(model.widget_ids.emitter_control.canvas, ui);
+
+    for _click in widget::Button::new()
+        .top_left_with_margins_on(model.widget_ids.emitter_control.canvas, 25.0, 0.0)
+        .w_h(98.0, 25.0)
+        .label("add")
+        .rgb(0.3, 0.3, 0.3)
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .border(0.0)
+        .set(model.widget_ids.emitter_control.add_button, ui)
+    {
+        model.emitters.push(Emitter::new(model.arena.center));
+        model.selected_emitter = model.emitters.len() - 1;
+    }
+
+    for _click in widget::Button::new()
+        .right_from(model.widget_ids.emitter_control.add_button, 4.0)
+        .w_h(98.0, 25.0)
+        .label("remove")
+        .rgb(0.3, 0.3, 0.3)
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .border(0.0)
+        .set(model.widget_ids.emitter_control.remove_button, ui)
+    {
+        if model.selected_emitter < model.emitters.len() {
+            model.emitters.remove(model.selected_emitter);
+            model.selected_emitter = model.selected_emitter.saturating_sub(1);
+        }
+    }
+
+    if model.selected_emitter < model.emitters.len() {
+        let i_emit = model.selected_emitter;
+        let half_w = model.arena.width / 2.0;
+        let half_h = model.arena.height / 2.0;
+        let rel = model.emitters[i_emit].position - model.arena.center;
+
+        let x_label = format!("x: {:.0}", rel.x);
+        for value in widget::Slider::new(rel.x, -half_w, half_w)
+            .down_from(model.widget_ids.emitter_control.add_button, 33.0)
+            .w_h(98.0, 25.0)
+            .label(&x_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.emitter_control.x, ui)
+        {
+            model.emitters[i_emit].position.x = model.arena.center.x + value;
+        }
+
+        let y_label = format!("y: {:.0}", rel.y);
+        for value in widget::Slider::new(rel.y, -half_h, half_h)
+            .right_from(model.widget_ids.emitter_control.x, 4.0)
+            .w_h(98.0, 25.0)
+            .label(&y_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.emitter_control.y, ui)
+        {
+            model.emitters[i_emit].position.y = model.arena.center.y + value;
+        }
+
+        let rate_label = format!("{:.2} balls / beat", model.emitters[i_emit].rate);
+        for value in widget::Slider::new(model.emitters[i_emit].rate, 0.0, 4.0)
+            .down_from(model.widget_ids.emitter_control.x, 4.0)
+            .w_h(200.0, 25.0)
+            .label(&rate_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.emitter_control.rate, ui)
+        {
+            model.emitters[i_emit].rate = value;
+        }
+
+        let direction_label = format!("dir: {:.0}°", model.emitters[i_emit].direction);
+        for value in widget::Slider::new(model.emitters[i_emit].direction, 0.0, 360.0)
+            .down_from(model.widget_ids.emitter_control.rate, 4.0)
+            .w_h(98.0, 25.0)
+            .label(&direction_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.emitter_control.direction, ui)
+        {
+            model.emitters[i_emit].direction = value.round();
+        }
+
+        let spread_label = format!("± {:.0}°", model.emitters[i_emit].spread);
+        for value in widget::Slider::new(model.emitters[i_emit].spread, 0.0, 180.0)
+            .right_from(model.widget_ids.emitter_control.direction, 4.0)
+            .w_h(98.0, 25.0)
+            .label(&spread_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.emitter_control.spread, ui)
+        {
+            model.emitters[i_emit].spread = value.round();
+        }
+
+        let speed_label = format!("speed: {:.1}", model.emitters[i_emit].speed);
+        for value in widget::Slider::new(model.emitters[i_emit].speed, 0.0, 15.0)
+            .down_from(model.widget_ids.emitter_control.direction, 4.0)
+            .w_h(98.0, 25.0)
+            .label(&speed_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.emitter_control.speed, ui)
+        {
+            model.emitters[i_emit].speed = value;
+        }
+
+        let speed_spread_label = format!("± {:.0} %", model.emitters[i_emit].speed_spread * 100.0);
+        for value in widget::Slider::new(model.emitters[i_emit].speed_spread, 0.0, 1.0)
+            .right_from(model.widget_ids.emitter_control.speed, 4.0)
+            .w_h(98.0, 25.0)
+            .label(&speed_spread_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.emitter_control.speed_spread, ui)
+        {
+            model.emitters[i_emit].speed_spread = value;
+        }
+
+        let lifetime_label = if model.emitters[i_emit].lifetime > 0.0 {
+            format!("lifetime: {:.1} s", model.emitters[i_emit].lifetime)
+        } else {
+            "lifetime: forever".to_string()
+        };
+        for value in widget::Slider::new(model.emitters[i_emit].lifetime, 0.0, 30.0)
+            .down_from(model.widget_ids.emitter_control.speed, 4.0)
+            .w_h(200.0, 25.0)
+            .label(&lifetime_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.emitter_control.lifetime, ui)
+        {
+            model.emitters[i_emit].lifetime = value;
+        }
+
+        let max_hits_label = if model.emitters[i_emit].max_hits > 0 {
+            format!("remove after {} hits", model.emitters[i_emit].max_hits)
+        } else {
+            "unlimited hits".to_string()
+        };
+        for value in widget::Slider::new(model.emitters[i_emit].max_hits as f32, 0.0, 32.0)
+            .down_from(model.widget_ids.emitter_control.lifetime, 4.0)
+            .w_h(200.0, 25.0)
+            .label(&max_hits_label)
+            .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+            .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+            .set(model.widget_ids.emitter_control.max_hits, ui)
+        {
+            model.emitters[i_emit].max_hits = value.round() as u32;
+        }
+
+        for value in widget::Toggle::new(model.emitters[i_emit].on_midi)
+            .down_from(model.widget_ids.emitter_control.max_hits, 4.0)
+            .w_h(200.0, 25.0)
+            .label("launch on MIDI in notes")
+            .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+            .rgb(0.3, 0.3, 0.3)
+            .set(model.widget_ids.emitter_control.on_midi, ui)
+        {
+            model.emitters[i_emit].on_midi = value;
+        }
+    }
+
+    // emitter selection -> after its controls for "overlay effect" when selecting
+    let emitter_names: Vec<String> = (1..=model.emitters.len())
+        .map(|n| format!("emitter {}", n))
+        .collect();
+    let selected_emitter = if model.emitters.is_empty() {
+        None
+    } else {
+        Some(model.selected_emitter)
+    };
+    for i in widget::DropDownList::new(&emitter_names, selected_emitter)
+        .down_from(model.widget_ids.emitter_control.add_button, 4.0)
+        .align_left_of(model.widget_ids.emitter_control.add_button)
+        .w_h(200.0, 25.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .label("no emitter")
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.emitter_control.select, ui)
+    {
+        model.selected_emitter = i;
+    }
+
+    // wall selection -> last for "overlay effect" when selecting
+    for i in widget::DropDownList::new(&wall_names, Some(wall_index))
+        .down_from(model.widget_ids.arena_control.rotation_synced, 4.0)
+        .w_h(200.0, 25.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.arena_control.wall_select, ui)
+    {
+        model.selected_wall = Wall::ALL[i];
+        model.controllers = midi::format_controllers(
+            &model
+                .ball_model
+                .interaction(model.selected_wall)
+                .controllers,
+        );
+    }
+
+    // virtual MIDI ports (left of the port lists), renaming applies on the next opening
+    for event in widget::TextBox::new(&model.midi.virtual_out_name)
+        .x_relative_to(model.widget_ids.midi_out_ports_list, -292.0)
+        .y_relative_to(model.widget_ids.midi_out_ports_list, 0.0)
+        .w_h(196.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .text_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 1.0))
+        .left_justify()
+        .set(model.widget_ids.virtual_out_name, ui)
+    {
+        if let nannou::ui::widget::text_box::Event::Update(txt) = event {
+            model.midi.virtual_out_name = txt;
+        }
+    }
+    for value in widget::Toggle::new(model.midi.virtual_out)
+        .right_from(model.widget_ids.virtual_out_name, 4.0)
+        .w_h(40.0, 25.0)
+        .label("virt")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .rgb(0.3, 0.3, 0.3)
+        .set(model.widget_ids.virtual_out_toggle, ui)
+    {
+        model.midi.virtual_out = if value {
+            open_virtual_output(&mut model.midi)
+        } else {
+            close_output(&mut model.midi);
+            false
+        };
+    }
+    for event in widget::TextBox::new(&model.midi.virtual_in_name)
+        .down_from(model.widget_ids.virtual_out_name, 4.0)
+        .w_h(196.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .text_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 1.0))
+        .left_justify()
+        .set(model.widget_ids.virtual_in_name, ui)
+    {
+        if let nannou::ui::widget::text_box::Event::Update(txt) = event {
+            model.midi.virtual_in_name = txt;
+        }
+    }
+    for value in widget::Toggle::new(model.midi.virtual_in)
+        .right_from(model.widget_ids.virtual_in_name, 4.0)
+        .w_h(40.0, 25.0)
+        .label("virt")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .rgb(0.3, 0.3, 0.3)
+        .set(model.widget_ids.virtual_in_toggle, ui)
+    {
+        model.midi.virtual_in = if value {
+            open_virtual_input(&mut model.midi)
+        } else {
+            model.midi.in_connection = None; // close the port
+            model.midi.selected_input = "no MIDI in selected".to_string();
+            false
+        };
+    }
+
+    // current MIDI out ports list
+    let mut port_list = Vec::<String>::new();
+    let o = MidiOutput::new("bouncyquencer MIDI out"); // '?' operator can't be used
+    if let Err(e) = o {
+        eprintln!("MIDI out error: {}", e);
+        return;
+    }
+    let midi_out = o.unwrap();
+    for i in 0..midi_out.port_count() {
+        let pname = midi_out
+            .port_name(i)
+            .expect("error retrieving MIDI out port name");
+        port_list.push(pname);
+    }
+    // MIDI out port dropdown list widget -> last for "overlay effect" when selecting
+    for i in widget::DropDownList::new(&port_list, None)
+        .x(-280.0)
+        .y(230.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .w(290.0) // absolute width
+        .h(25.0) // absolute height
+        .label(&model.midi.selected_output) // currently selected port / device
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.midi_out_ports_list, ui)
+    // attach widget to UI
+    {
+        // process selection index emitted from the list
+        let midi_out = match MidiOutput::new("bouncyquencer MIDI out") {
+            Ok(o) => o,
+            Err(e) => {
+                eprintln!("MIDI out error: {}", e);
+                continue;
+            }
+        };
+        match midi_out.connect(i, "bouncyquencer out") {
+            Ok(c) => {
+                release_port(&mut model.midi, "");
+                model.midi.out_connection = Some(c);
+                model.midi.selected_output = port_list[i].clone();
+                model.midi.out_port_number = i;
+                model.midi.virtual_out = false; // replaced by the selected port
+            }
+            Err(e) => eprintln!("MIDI out error: {}", e),
+        }
+    }
+
+    // current MIDI in ports list
+    let mut in_port_list = Vec::<String>::new();
+    match midir::MidiInput::new("bouncyquencer MIDI in") {
+        Ok(midi_in) => {
+            for i in 0..midi_in.port_count() {
+                let pname = midi_in
+                    .port_name(i)
+                    .expect("error retrieving MIDI in port name");
+                in_port_list.push(pname);
+            }
+        }
+        Err(e) => eprintln!("MIDI in error: {}", e),
+    }
+    for i in widget::DropDownList::new(&in_port_list, None)
+        .down_from(model.widget_ids.midi_out_ports_list, 4.0)
+        .border(1.0)
+        .border_color(Rgba(1.0, 1.0, 1.0, 0.5)) // TODO: adjust colour to scheme
+        .scrollbar_next_to() // scrollbar on the right
+        .w(290.0) // absolute width
+        .h(25.0) // absolute height
+        .label(&model.midi.selected_input) // currently selected port / device
+        .label_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8))
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0) // grey selection background
+        .set(model.widget_ids.midi_in_ports_list, ui)
+    {
+        model.midi.in_connection = None; // close previous connection
+        let midi_in = midir::MidiInput::new("bouncyquencer MIDI in");
+        if let Err(e) = midi_in {
+            eprintln!("MIDI in error: {}", e);
+            continue;
+        }
+        let connection = midi_in.unwrap().connect(
+            i,
+            "bouncyquencer in",
+            forward_note_on,
+            model.midi.in_sender.clone(),
+        );
+        match connection {
+            Ok(c) => {
+                model.midi.in_connection = Some(c);
+                model.midi.selected_input = in_port_list[i].clone();
+                model.midi.virtual_in = false; // replaced by the selected port
+            }
+            Err(e) => eprintln!("MIDI in error: {}", e),
+        }
+    }
+
+    let mut events = Vec::<Event>::new();
+    let mut hits = Vec::<HitState>::new(); // for the state stream
+                                           // notes received since the last update (discarded while stopped)
+    let midi_notes = model.midi.in_receiver.try_iter().count();
+    if model.playing {
+        // the arena
+        model
+            .arena
+            .update(update.since_last.as_secs_f32(), model.bpm);
+        // what gravity adds within an update only presses a ball onto the floor
+        model.arena.gravity = model.physics.gravity_vector();
+
+        // the ball
+        let step = step_ball(
+            &mut model.ball_model.ball,
+            0,
+            &model.physics,
+            &model.arena,
+            &mut model.attractors,
+        );
+        hits.extend(step.hits.iter().map(|hit| HitState::new(0, hit)));
+        events.extend(ball_events(
+            &model.ball_model,
+            &model.key,
+            &model.drum_maps[model.drum_map],
+            &model.tuning,
+            &model.arena,
+            &model.attractors,
+            &model.attractor_port,
+            &model.ball_model.ball,
+            &step,
+            update.since_start,
+            update.since_last,
+        ));
+        let v = model.ball_model.ball.get_velocity();
+        model.ball_model.velocity_x = v.x;
+        model.ball_model.velocity_y = v.y;
+        model.rhythm_preview = rhythm::predict(
+            &model.arena,
+            &model.ball_model.ball,
+            model.preview_hits,
+            rhythm::to_updates(model.preview_beats, model.bpm),
+        );
+
+        // the emitters & their balls
+        let dt = update.since_last.as_secs_f32();
+        for emitter in model.emitters.iter_mut() {
+            let mut due = emitter.update(dt, model.bpm);
+            if emitter.on_midi {
+                due += midi_notes;
+            }
+            for _ in 0..due {
+                if model.emitted_balls.len() >= MAX_EMITTED_BALLS {
+                    break;
+                }
+                model
+                    .emitted_balls
+                    .push(EmittedBall::new(model.next_ball_id, emitter));
+                model.next_ball_id += 1;
+            }
+        }
+        for emitted in model.emitted_balls.iter_mut() {
+            let step = step_ball(
+                &mut emitted.ball,
+                emitted.id,
+                &model.physics,
+                &model.arena,
+                &mut model.attractors,
+            );
+            hits.extend(step.hits.iter().map(|hit| HitState::new(emitted.id, hit)));
+            events.extend(ball_events(
+                &model.ball_model,
+                &model.key,
+                &model.drum_maps[model.drum_map],
+                &model.tuning,
+                &model.arena,
+                &model.attractors,
+                &model.attractor_port,
+                &emitted.ball,
+                &step,
+                update.since_start,
+                update.since_last,
+            ));
+            emitted.hits += step.hits.len() as u32;
+            emitted.absorbed |= step.hits.iter().any(|hit| hit.kind == HitKind::Absorb);
+            emitted.age += dt;
+        }
+        let attractors = &mut model.attractors;
+        model.emitted_balls.retain(|emitted| {
+            if emitted.expired() {
+                for attractor in attractors.iter_mut() {
+                    attractor.forget(emitted.id);
+                }
+                return false;
+            }
+            true
+        });
+        dispatch(
+            &mut model.midi,
+            &mut model.pd,
+            &mut model.osc,
+            &mut model.log,
+            &model.routes,
+            &events,
+            update.since_start,
+        );
+    }
+    if let Some(websocket) = model.websocket.as_mut() {
+        let mut balls = vec![BallState::new(0, &model.ball_model.ball)];
+        balls.extend(
+            model
+                .emitted_balls
+                .iter()
+                .map(|emitted| BallState::new(emitted.id, &emitted.ball)),
+        );
+        websocket.send(&Report::State {
+            time: update.since_start.as_secs_f32(),
+            playing: model.playing,
+            balls,
+            hits,
+        });
+        for event in events.iter() {
+            websocket.send(&Report::Note(NoteState::new(event)));
+        }
+    }
+
+    // --- end GUI code --- //
+
+    // --- begin MIDI code --- //
+    while let Some(tm) = model.midi.time_queue.peek() {
+        // see if it is (past) time to send message
+        if update.since_start < tm.timestamp {
+            break;
+        }
+        let msg = model.midi.time_queue.pop().unwrap();
+        send_midi(&mut model.midi, msg);
+    }
+    // --- end code --- //
+
+    // --- begin menue
+    // -- start GUI project name
+    for event in widget::TextBox::new(&model.project_name)
+        .x_relative_to(model.widget_ids.midi_out_ports_list, 270.0)
+        .y_relative_to(model.widget_ids.midi_out_ports_list, 0.0)
+        .w_h(200.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .text_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 1.0))
+        .left_justify()
+        .set(model.widget_ids.menue.project_name_textbox, ui)
+    {
+        if let nannou::ui::widget::text_box::Event::Update(txt) = event {
+            model.project_name = txt;
+        }
+    }
+    // -- end GUI project name
+
+    // -- start GUI save/load
+    for _ in widget::Button::new()
+        .x_relative_to(model.widget_ids.menue.project_name_textbox, 170.0)
+        .y_relative_to(model.widget_ids.menue.project_name_textbox, 0.0)
+        .w_h(75.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .label("save")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .set(model.widget_ids.menue.save_button, ui)
+    {
+        model.do_save_state = true;
+    }
+
+    for _ in widget::Button::new()
+        .x_relative_to(model.widget_ids.menue.save_button, 100.0)
+        .y_relative_to(model.widget_ids.menue.save_button, 0.0)
+        .w_h(75.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .label("load")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .set(model.widget_ids.menue.load_button, ui)
+    {
+        model.do_load_state = true; // run model state in next run before doing GUI work
+    }
+    // -- end GUI save/load
+
+    // -- start GUI transport & remote control
+    for _ in widget::Button::new()
+        .down_from(model.widget_ids.menue.project_name_textbox, 4.0)
+        .w_h(98.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .label(if model.playing { "stop" } else { "play" })
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .set(model.widget_ids.menue.play_button, ui)
+    {
+        model.playing = !model.playing;
+        if !model.playing {
+            release_all(&mut model.midi);
+        }
+    }
+
+    // OSC remote control
+    for value in widget::Toggle::new(model.remote.is_some())
+        .right_from(model.widget_ids.menue.play_button, 4.0)
+        .w_h(47.0, 25.0)
+        .label("OSC")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .rgb(0.3, 0.3, 0.3)
+        .set(model.widget_ids.menue.remote_toggle, ui)
+    {
+        model.remote = None; // free the port first
+        if value {
+            model.remote = listen_remote(&model.remote_port, model.remote_lan);
+        }
+    }
+
+    // port to listen on for OSC commands (press enter to apply)
+    for event in widget::TextBox::new(&model.remote_port)
+        .right_from(model.widget_ids.menue.remote_toggle, 4.0)
+        .w_h(47.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .text_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 1.0))
+        .left_justify()
+        .set(model.widget_ids.menue.remote_port_textbox, ui)
+    {
+        match event {
+            nannou::ui::widget::text_box::Event::Update(txt) => model.remote_port = txt,
+            nannou::ui::widget::text_box::Event::Enter => {
+                if model.remote.is_some() {
+                    model.remote = None; // free the old port first
+                    model.remote = listen_remote(&model.remote_port, model.remote_lan);
+                }
+            }
+        }
+    }
+
+    // FUDI remote control from pure data
+    for value in widget::Toggle::new(model.fudi.is_some())
+        .right_from(model.widget_ids.menue.remote_port_textbox, 4.0)
+        .w_h(47.0, 25.0)
+        .label("FUDI")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .rgb(0.3, 0.3, 0.3)
+        .set(model.widget_ids.menue.fudi_toggle, ui)
+    {
+        model.fudi = None; // free the port first
+        if value {
+            model.fudi = listen_fudi(&model.fudi_port, model.remote_lan);
+        }
+    }
+
+    // port to listen on for FUDI commands from pure data (press enter to apply)
+    for event in widget::TextBox::new(&model.fudi_port)
+        .right_from(model.widget_ids.menue.fudi_toggle, 4.0)
+        .w_h(47.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .text_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 1.0))
+        .left_justify()
+        .set(model.widget_ids.menue.fudi_port_textbox, ui)
+    {
+        match event {
+            nannou::ui::widget::text_box::Event::Update(txt) => model.fudi_port = txt,
+            nannou::ui::widget::text_box::Event::Enter => {
+                if model.fudi.is_some() {
+                    model.fudi = None; // free the old port first
+                    model.fudi = listen_fudi(&model.fudi_port, model.remote_lan);
+                }
+            }
+        }
+    }
+
+    // stream the state to visualisers & accept their commands
+    for value in widget::Toggle::new(model.websocket.is_some())
+        .down_from(model.widget_ids.menue.play_button, 4.0)
+        .w_h(98.0, 25.0)
+        .label("websocket")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .rgb(0.3, 0.3, 0.3)
+        .set(model.widget_ids.menue.websocket_toggle, ui)
+    {
+        model.websocket = if value {
+            listen_websocket(&model.websocket_port, model.remote_lan)
+        } else {
+            None
+        };
+    }
+
+    // port for the state stream (press enter to apply)
+    for event in widget::TextBox::new(&model.websocket_port)
+        .right_from(model.widget_ids.menue.websocket_toggle, 4.0)
+        .w_h(98.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .text_color(Rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 1.0))
+        .left_justify()
+        .set(model.widget_ids.menue.websocket_port_textbox, ui)
+    {
+        match event {
+            nannou::ui::widget::text_box::Event::Update(txt) => model.websocket_port = txt,
+            nannou::ui::widget::text_box::Event::Enter => {
+                if model.websocket.is_some() {
+                    model.websocket = None; // free the old port first
+                    model.websocket = listen_websocket(&model.websocket_port, model.remote_lan);
+                }
+            }
+        }
+    }
+
+    // listen on all network interfaces instead of this computer only
+    for value in widget::Toggle::new(model.remote_lan)
+        .right_from(model.widget_ids.menue.websocket_port_textbox, 4.0)
+        .w_h(98.0, 25.0)
+        .label("LAN access")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .rgb(0.3, 0.3, 0.3)
+        .set(model.widget_ids.menue.remote_lan_toggle, ui)
+    {
+        model.remote_lan = value;
+        if model.remote.is_some() {
+            model.remote = None; // free the port first
+            model.remote = listen_remote(&model.remote_port, model.remote_lan);
+        }
+        if model.fudi.is_some() {
+            model.fudi = None;
+            model.fudi = listen_fudi(&model.fudi_port, model.remote_lan);
+        }
+        if model.websocket.is_some() {
+            model.websocket = None;
+            model.websocket = listen_websocket(&model.websocket_port, model.remote_lan);
+        }
+    }
+    // -- end GUI transport & remote control
+
+    // -- start GUI panic
+    for _ in widget::Button::new()
+        .down_from(model.widget_ids.menue.about_button, 4.0)
+        .w_h(75.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .label("panic")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .set(model.widget_ids.menue.panic_button, ui)
+    {
+        midi_panic(&mut model.midi);
+    }
+    // -- end GUI panic
+
+    // -- start GUI about
+    for _ in widget::Button::new()
+        .x_relative_to(model.widget_ids.menue.load_button, 100.0)
+        .y_relative_to(model.widget_ids.menue.load_button, 0.0)
+        .w_h(75.0, 25.0)
+        .rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 1.0)
+        .border(0.0)
+        .label("about")
+        .label_rgb(255.0 / 255.0, 242.0 / 255.0, 0.0)
+        .set(model.widget_ids.menue.about_button, ui)
+    {
+        // toggle display about text
+        model.should_display_about = !model.should_display_about;
+        model.should_display_mit_license = false;
+    }
+
+    if model.should_display_about {
+        // the canvas to group widgets & control background etc.
         widget::Canvas::new()
             .x(0.0)
             .y(0.0)
@@ -1168,7 +3612,8 @@ This work builds on the effort of others, namely:
 * midir - https://github.com/Boddlnagg/midir (licensed under the MIT License)
 * wmidi - https://github.com/wmedrano/wmidi (licensed under the MIT License)
 * serde - https://serde.rs (licensed under the MIT License)
-* serde_json - https://github.com/serde-rs/json (licensed under the MIT License)",
+* serde_json - https://github.com/serde-rs/json (licensed under the MIT License)
+* tungstenite - https://github.com/snapview/tungstenite-rs (licensed under the MIT License)",
         )
         .y_relative_to(model.widget_ids.menue.about_text_close_button, 130.0)
         .x(0.0)
@@ -1242,30 +3687,779 @@ This work builds on the effort of others, namely:
     // --- end menue
 }
 
+/// What happened to a ball during one update.
+struct BallStep {
+    id: u64,            // the ball (0 = main ball)
+    hits: Vec<Hit>,     // walls reached
+    passes: Vec<usize>, // attractors passed (indices)
+}
+
+/// Move a ball by one update: apply the forces, let it interact
+/// with the walls and track the attractors it passes.
+fn step_ball(
+    ball: &mut Ball,
+    id: u64,
+    physics: &Physics,
+    arena: &Arena,
+    attractors: &mut [Attractor],
+) -> BallStep {
+    physics.apply(ball, attractors, arena.supports(ball)); // forces change the velocity
+    let hits = arena.advance(ball); // move through the walls it reaches
+
+    let pos = ball.get_position();
+    let mut passes = Vec::<usize>::new();
+    for (i, attractor) in attractors.iter_mut().enumerate() {
+        if attractor.passes(id, pos) {
+            passes.push(i);
+        }
+    }
+    BallStep { id, hits, passes }
+}
+
+/// Turn everything a ball did into events (skipping things without a note).
+/// Hits are timed to the moment within the update they happened at.
+fn ball_events(
+    ball_model: &BallModel,
+    key: &Key,
+    drums: &DrumMap,
+    tuning: &Tuning,
+    arena: &Arena,
+    attractors: &[Attractor],
+    attractor_port: &str,
+    ball: &Ball,
+    step: &BallStep,
+    trigger: Duration,
+    frame: Duration, // length of the update
+) -> Vec<Event> {
+    let v = ball.get_velocity();
+    let speed = (v.x * v.x + v.y * v.y).sqrt();
+    let (half_width, half_height) = (arena.width / 2.0, arena.height / 2.0);
+    let distance = |p: Point2| {
+        (p.x.powi(2) + p.y.powi(2)).sqrt() / (half_width.powi(2) + half_height.powi(2)).sqrt()
+    };
+
+    let mut events = Vec::<Event>::new();
+    for hit in step.hits.iter() {
+        let interaction = ball_model.hit_interaction(hit);
+        if "None" == interaction.note_display {
+            continue;
+        }
+        // where the ball touched the wall, not where it is at the end of the update
+        let position = hit.position;
+        let along = match hit.wall {
+            Wall::Top | Wall::Bottom => position.x / half_width,
+            Wall::Left | Wall::Right => position.y / half_height,
+        };
+        let (note, frequency, bend) = if interaction.is_drum() {
+            // the zones of a drum wall are the following drums of the map
+            let zone = interaction.zones.zone(along);
+            let note = drums.step_up(interaction.note(key), zone);
+            (note, tuning::frequency(note as f32), None) // drums are not tuned
+        } else {
+            let (note, bend) = interaction.zones.pitch(interaction.note(key), along, key);
+            match tuning.tune(note, bend) {
+                Some((frequency, bend)) => (note, frequency, bend),
+                None => continue, // not played by the keyboard of the tuning
+            }
+        };
+        events.push(Event {
+            ball: step.id,
+            source: Source::Wall(hit.wall, hit.kind),
+            note,
+            velocity: interaction.velocity,
+            channel: interaction.midi_channel,
+            port: interaction.midi_port.clone(), // routed per wall, wrapping & portals
+            program: Some(interaction.program).filter(|p| *p < 128),
+            controllers: interaction.controllers.clone(),
+            length: interaction.length,
+            position,
+            along,
+            bend,
+            frequency,
+            distance: distance(position),
+            speed,
+            time: trigger + frame.mul_f32(hit.time),
+        });
+    }
+    let position = arena.to_local(ball.get_position());
+    for i in step.passes.iter() {
+        let attractor = &attractors[*i];
+        if attractor.midi_note > 127 {
+            continue;
+        }
+        let (note, frequency, bend) = if attractor.midi_channel == drums::CHANNEL {
+            let note = attractor.midi_note;
+            (note, tuning::frequency(note as f32), None)
+        } else {
+            let note = key.transpose_from(attractor.note_root, attractor.midi_note);
+            match tuning.tune(note, None) {
+                Some((frequency, bend)) => (note, frequency, bend),
+                None => continue,
+            }
+        };
+        events.push(Event {
+            ball: step.id,
+            source: Source::Attractor(*i),
+            note,
+            velocity: attractor.velocity,
+            channel: attractor.midi_channel,
+            port: attractor_port.to_string(),
+            program: None,
+            controllers: vec![],
+            length: attractor.length,
+            position,
+            along: 0.0,
+            bend,
+            frequency,
+            distance: distance(position),
+            speed,
+            time: trigger,
+        });
+    }
+    events
+}
+
+/// Send events to all active backends (as far as their routes allow).
+fn dispatch(
+    midi: &mut MidiConnector,
+    pd: &mut PdConnector,
+    osc: &mut OscConnector,
+    log: &mut LogSink,
+    routes: &[Route; 4],
+    events: &[Event],
+    now: Duration,
+) {
+    for backend in Backend::ALL.iter() {
+        let route = routes[backend.index()];
+        if !route.active {
+            continue;
+        }
+        let sink: &mut dyn EventSink = match backend {
+            Backend::Midi => &mut *midi,
+            Backend::Pd => match pd.ball.as_mut() {
+                Some(b) => b,
+                None => continue, // not connected
+            },
+            Backend::Osc => match osc.ball.as_mut() {
+                Some(b) => b,
+                None => continue,
+            },
+            Backend::Log => &mut *log,
+        };
+        for event in events.iter().filter(|e| route.accepts(e)) {
+            if let Err(e) = sink.send(event, now) {
+                eprintln!("{} error: {}", sink.name(), e);
+            }
+        }
+    }
+}
+
+/// Create the OSC sender, if the target (host:port) is valid.
+fn connect_osc(target: &str, bundled: bool) -> Option<OscBall> {
+    match OscBall::connect(target) {
+        Ok(mut osc_ball) => {
+            osc_ball.set_bundled(bundled);
+            Some(osc_ball)
+        }
+        Err(e) => {
+            eprintln!("OSC error: invalid target {}: {}", target, e);
+            None
+        }
+    }
+}
+
+/// Create the sender for pure data, if the target (host:port) is valid.
+fn connect_pd(target: &str) -> Option<PdBall> {
+    match target.to_socket_addrs() {
+        Ok(_) => Some(PdBall::new(target)),
+        Err(e) => {
+            eprintln!("Pd error: invalid target {}: {}", target, e);
+            None
+        }
+    }
+}
+
+/// Put note on & off messages into the send queue.
+fn queue_note(
+    midi: &mut MidiConnector,
+    port: &str,
+    channel: u8,
+    note: u8,
+    velocity: u8,
+    trigger: Duration,
+    length: u64, // in ms
+) {
+    let (on, off) = TimedMidiMessage::create_on_off(
+        port,
+        channel,
+        note,
+        velocity,
+        trigger,
+        Duration::from_millis(length),
+    );
+    midi.time_queue.push(on);
+    midi.time_queue.push(off);
+}
+
+/// Send a MIDI message to its port, dropped if the port is not (or no longer) open.
+/// A note off is left out while another hit still holds the same note.
+fn send_midi(midi: &mut MidiConnector, msg: TimedMidiMessage) {
+    match msg.r#type {
+        TimedMidiMessageType::NoteOn => midi.active_notes.note_on(&msg.port, msg.channel, msg.note),
+        TimedMidiMessageType::NoteOff => {
+            if !midi.active_notes.note_off(&msg.port, msg.channel, msg.note) {
+                return;
+            }
+        }
+        _ => {}
+    }
+    send_message(midi, &msg);
+}
+
+/// Send a MIDI message right away (unless it is invalid).
+fn send_message(midi: &mut MidiConnector, msg: &TimedMidiMessage) {
+    match msg.to_bytes() {
+        Ok(bytes) => send_bytes(midi, &msg.port, &bytes),
+        Err(e) => eprintln!("MIDI out error: {}", e),
+    }
+}
+
+/// Send raw MIDI bytes to a port ("" = default output).
+fn send_bytes(midi: &mut MidiConnector, port: &str, bytes: &[u8]) {
+    let connection = if port.is_empty() {
+        match midi.out_connection.as_mut() {
+            Some(connection) => connection,
+            None => return,
+        }
+    } else {
+        match midi.outputs.iter_mut().find(|o| o.name == port) {
+            Some(output) => &mut output.connection,
+            None => return,
+        }
+    };
+    if let Err(e) = connection.send(bytes) {
+        eprintln!("MIDI out error: {}", e);
+    }
+}
+
+/// Stop the sounding notes of a port (before it is closed or replaced).
+fn release_port(midi: &mut MidiConnector, port: &str) {
+    for (channel, note) in midi.active_notes.release_port(port) {
+        send_message(midi, &TimedMidiMessage::note_off(port, channel, note));
+    }
+}
+
+/// Drop the queued notes and stop all sounding ones.
+fn release_all(midi: &mut MidiConnector) {
+    midi.time_queue.clear();
+    for (port, channel, note) in midi.active_notes.release_all() {
+        send_message(midi, &TimedMidiMessage::note_off(&port, channel, note));
+    }
+}
+
+/// Tune every key retuned via MTS back to its own pitch (after the tuning
+/// changed or on panic).
+fn reset_tunings(midi: &mut MidiConnector) {
+    for (port, key) in midi.tunings.reset_all() {
+        let tuning = midi::note_tuning(key, key as f32);
+        let message = TimedMidiMessage::new(tuning, &port, 1, Duration::from_millis(0));
+        send_message(midi, &message);
+    }
+}
+
+/// Silence everything: stop all notes and send "all notes off" on every
+/// channel of every open port (in case notes were lost elsewhere).
+fn midi_panic(midi: &mut MidiConnector) {
+    release_all(midi);
+    reset_tunings(midi);
+    let mut ports = vec![String::new()];
+    ports.extend(midi.outputs.iter().map(|o| o.name.clone()));
+    for port in ports.iter() {
+        for bytes in midi::all_notes_off().iter() {
+            send_bytes(midi, port, bytes);
+        }
+    }
+}
+
+/// Names of the MIDI out ports of the system.
+fn output_port_names() -> Vec<String> {
+    let mut names = Vec::<String>::new();
+    match MidiOutput::new("bouncyquencer MIDI out") {
+        Ok(midi_out) => {
+            for i in 0..midi_out.port_count() {
+                if let Ok(name) = midi_out.port_name(i) {
+                    names.push(name);
+                }
+            }
+        }
+        Err(e) => eprintln!("MIDI out error: {}", e),
+    }
+    names
+}
+
+/// Open a MIDI out port by its name.
+fn open_output_port(name: &str) -> Result<midir::MidiOutputConnection, String> {
+    let midi_out = MidiOutput::new("bouncyquencer MIDI out").map_err(|e| e.to_string())?;
+    let index = (0..midi_out.port_count())
+        .find(|i| midi_out.port_name(*i).ok().as_deref() == Some(name))
+        .ok_or_else(|| format!("port {} is missing", name))?;
+    midi_out
+        .connect(index, "bouncyquencer out")
+        .map_err(|e| e.to_string())
+}
+
+/// Open an additional MIDI out port (unless it is open already).
+fn open_additional_output(midi: &mut MidiConnector, name: &str) -> bool {
+    if midi.outputs.iter().any(|o| o.name == name) {
+        return true;
+    }
+    match open_output_port(name) {
+        Ok(connection) => {
+            midi.outputs.push(MidiPort {
+                name: name.to_string(),
+                connection,
+            });
+            true
+        }
+        Err(e) => {
+            eprintln!("MIDI out error: {}", e);
+            false
+        }
+    }
+}
+
+/// Close an additional MIDI out port, the event sources routed to it
+/// go to the default output again.
+fn close_additional_output(
+    midi: &mut MidiConnector,
+    ball_model: &mut BallModel,
+    attractor_port: &mut String,
+    name: &str,
+) {
+    release_port(midi, name);
+    midi.outputs.retain(|o| o.name != name);
+    for source in 0..PORT_SOURCES.len() {
+        let port = source_port(ball_model, attractor_port, source);
+        if port == name {
+            *port = String::new();
+        }
+    }
+}
+
+/// MIDI out port of an event source (index into PORT_SOURCES).
+fn source_port<'a>(
+    ball_model: &'a mut BallModel,
+    attractor_port: &'a mut String,
+    source: usize,
+) -> &'a mut String {
+    match source {
+        0..=3 => &mut ball_model.interaction_mut(Wall::ALL[source]).midi_port,
+        4 => &mut ball_model.wrap_interaction.midi_port,
+        5 => &mut ball_model.teleport_interaction.midi_port,
+        _ => attractor_port,
+    }
+}
+
+/// Switch to another key. The chosen notes of all walls & attractors stay,
+/// they are moved into the key when played (drums stay as they are).
+fn change_key(ball_model: &mut BallModel, key: &mut Key, new: Key, drums: &DrumMap) {
+    *key = new;
+    ball_model.relabel_notes(key, drums);
+}
+
+/// Change the model as requested by a remote controller.
+fn apply_command(model: &mut Model, command: Command) {
+    match command {
+        Command::BallVelocity(id, x, y) => {
+            let v = pt2(x, y);
+            if id == 0 {
+                model.ball_model.ball.set_velocity(v);
+                model.ball_model.velocity_x = x;
+                model.ball_model.velocity_y = y;
+            } else if let Some(emitted) = model.emitted_balls.iter_mut().find(|e| e.id == id) {
+                emitted.ball.set_velocity(v);
+            }
+        }
+        Command::WallNote(wall, note) => {
+            let interaction = model.ball_model.interaction_mut(wall);
+            interaction.set_note(note, &model.key, &model.drum_maps[model.drum_map]);
+        }
+        Command::WallVelocity(wall, velocity) => {
+            model.ball_model.interaction_mut(wall).velocity = velocity;
+        }
+        Command::WallLength(wall, length) => {
+            model.ball_model.interaction_mut(wall).length = length;
+        }
+        Command::WallProgram(wall, program) => {
+            model.ball_model.interaction_mut(wall).program = program;
+        }
+        Command::WallController(wall, controller, value) => {
+            let controllers = &mut model.ball_model.interaction_mut(wall).controllers;
+            match controllers.iter_mut().find(|(c, _)| *c == controller) {
+                Some(entry) => entry.1 = value,
+                None => controllers.push((controller, value)),
+            }
+            if wall == model.selected_wall {
+                model.controllers = midi::format_controllers(controllers);
+            }
+        }
+        Command::WallZones(wall, zones) => {
+            model.ball_model.interaction_mut(wall).zones = zones;
+        }
+        Command::WallChannel(wall, channel) => {
+            let interaction = model.ball_model.interaction_mut(wall);
+            interaction.midi_channel = channel;
+            interaction.relabel(&model.key, &model.drum_maps[model.drum_map]);
+        }
+        Command::Tempo(bpm) => model.bpm = bpm.max(30.0).min(300.0), // range of the slider
+        Command::Reference(frequency) => model.tuning.set_reference(frequency),
+        Command::ArenaSize(width, height) => {
+            let min = 2.0 * model.ball_model.ball.get_radius() + 1.0; // room for the ball
+            model.arena.width = width.max(min);
+            model.arena.height = height.max(min);
+        }
+        Command::Play => model.playing = true,
+        Command::Stop => {
+            model.playing = false;
+            release_all(&mut model.midi);
+        }
+        Command::LoadProject(name) => {
+            model.project_name = name;
+            model.do_load_state = true;
+        }
+        Command::SaveProject(name) => {
+            model.project_name = name;
+            model.do_save_state = true;
+        }
+    }
+}
+
+/// Pass on incoming note on messages (with velocity > 0) from the MIDI thread.
+fn forward_note_on(_stamp: u64, bytes: &[u8], sender: &mut mpsc::Sender<u8>) {
+    if bytes.len() == 3 && bytes[0] & 0xF0 == 0x90 && bytes[2] > 0 {
+        sender.send(bytes[1]).ok();
+    }
+}
+
+/// Stop sending to the current MIDI output.
+fn close_output(midi: &mut MidiConnector) {
+    release_port(midi, "");
+    midi.out_connection = None; // dropping the connection closes a virtual port
+    midi.selected_output = "no MIDI out selected".to_string();
+}
+
+/// Create a virtual MIDI output other applications can connect to
+/// (ALSA / JACK / CoreMIDI). Returns whether the port is open.
+#[cfg(unix)]
+fn open_virtual_output(midi: &mut MidiConnector) -> bool {
+    use midir::os::unix::VirtualOutput;
+    let connection = match MidiOutput::new("bouncyquencer MIDI out") {
+        Ok(o) => o.create_virtual(&midi.virtual_out_name),
+        Err(e) => {
+            eprintln!("MIDI out error: {}", e);
+            return false;
+        }
+    };
+    match connection {
+        Ok(c) => {
+            release_port(midi, "");
+            midi.out_connection = Some(c);
+            midi.selected_output = format!("virtual: {}", midi.virtual_out_name);
+            true
+        }
+        Err(e) => {
+            eprintln!("MIDI out error: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn open_virtual_output(_midi: &mut MidiConnector) -> bool {
+    eprintln!("MIDI out error: virtual ports are not supported on this platform");
+    false
+}
+
+/// Create a virtual MIDI input other applications can send notes to
+/// (ALSA / JACK / CoreMIDI). Returns whether the port is open.
+#[cfg(unix)]
+fn open_virtual_input(midi: &mut MidiConnector) -> bool {
+    use midir::os::unix::VirtualInput;
+    midi.in_connection = None; // close previous connection
+    let connection = match midir::MidiInput::new("bouncyquencer MIDI in") {
+        Ok(i) => i.create_virtual(
+            &midi.virtual_in_name,
+            forward_note_on,
+            midi.in_sender.clone(),
+        ),
+        Err(e) => {
+            eprintln!("MIDI in error: {}", e);
+            return false;
+        }
+    };
+    match connection {
+        Ok(c) => {
+            midi.in_connection = Some(c);
+            midi.selected_input = format!("virtual: {}", midi.virtual_in_name);
+            true
+        }
+        Err(e) => {
+            eprintln!("MIDI in error: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn open_virtual_input(_midi: &mut MidiConnector) -> bool {
+    eprintln!("MIDI in error: virtual ports are not supported on this platform");
+    false
+}
+
+/// Address to listen on, other computers can only connect with LAN access.
+fn listen_host(lan: bool) -> &'static str {
+    if lan {
+        ANY_HOST
+    } else {
+        LOCAL_HOST
+    }
+}
+
+/// Listen for OSC commands on the given port.
+fn listen_remote(port: &str, lan: bool) -> Option<OscRemote> {
+    let port = match port.parse::<u16>() {
+        Ok(port) => port,
+        Err(e) => {
+            eprintln!("OSC remote error: invalid port {}: {}", port, e);
+            return None;
+        }
+    };
+    match OscRemote::new(listen_host(lan), port) {
+        Ok(remote) => Some(remote),
+        Err(e) => {
+            eprintln!("OSC remote error: could not listen on port {}: {}", port, e);
+            None
+        }
+    }
+}
+
+/// Listen for FUDI commands (from pure data) on the given port.
+fn listen_fudi(port: &str, lan: bool) -> Option<FudiReceiver> {
+    let port = match port.parse::<u16>() {
+        Ok(port) => port,
+        Err(e) => {
+            eprintln!("FUDI error: invalid port {}: {}", port, e);
+            return None;
+        }
+    };
+    match FudiReceiver::new(listen_host(lan), port) {
+        Ok(fudi) => Some(fudi),
+        Err(e) => {
+            eprintln!("FUDI error: could not listen on port {}: {}", port, e);
+            None
+        }
+    }
+}
+
+/// Start the WebSocket state stream on the given port.
+fn listen_websocket(port: &str, lan: bool) -> Option<StateServer> {
+    let port = match port.parse::<u16>() {
+        Ok(port) => port,
+        Err(e) => {
+            eprintln!("WebSocket error: invalid port {}: {}", port, e);
+            return None;
+        }
+    };
+    match StateServer::new(listen_host(lan), port) {
+        Ok(server) => Some(server),
+        Err(e) => {
+            eprintln!("WebSocket error: could not listen on port {}: {}", port, e);
+            None
+        }
+    }
+}
+
 /// Draw model state on the screen.
 fn view_handler(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
     draw.background()
         .rgb(29.0 / 255.0, 43.0 / 255.0, 44.0 / 255.0); // black-ish background
+    display_arena(&model.arena, &draw); // draw arena
+    display_zones(&model.arena, &model.ball_model, &draw); // draw pitch zones
+    for attractor in model.attractors.iter() {
+        attractor.display(&draw); // draw attractors
+    }
+    for emitter in model.emitters.iter() {
+        emitter.display(&draw); // draw emitters
+    }
+    for emitted in model.emitted_balls.iter() {
+        emitted.ball.display(&draw); // draw launched balls
+    }
     model.ball_model.ball.display(&draw); // draw ball
+    let preview_area = nannou::geom::Rect::from_x_y_w_h(
+        model.arena.center.x + 270.0,
+        model.arena.center.y + 25.0,
+        260.0,
+        140.0,
+    );
+    display_rhythm(
+        &model.rhythm_preview,
+        &model.ball_model,
+        model.bpm,
+        model.preview_beats,
+        preview_area,
+        &draw,
+    ); // draw rhythm preview
     draw.to_frame(app, &frame).unwrap(); // draw app content
     model.ui.draw_to_frame(app, &frame).unwrap(); // draw UI
 }
 
+/// Draw predicted hits as a piano roll: one row per wall, one column per beat.
+fn display_rhythm(
+    hits: &[Hit],
+    ball_model: &BallModel,
+    bpm: f32,
+    beats: f32,
+    area: nannou::geom::Rect,
+    draw: &Draw,
+) {
+    let grey = nannou::color::rgba(119.0 / 255.0, 129.0 / 255.0, 135.0 / 255.0, 0.3);
+    let yellow = nannou::color::rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.8);
+    draw.rect().xy(area.xy()).wh(area.wh()).color(grey);
+
+    // beat grid
+    for beat in 0..=(beats as usize) {
+        let x = area.left() + beat as f32 / beats * area.w();
+        draw.line()
+            .start(pt2(x, area.bottom()))
+            .end(pt2(x, area.top()))
+            .weight(1.0)
+            .color(grey);
+    }
+
+    // one row per wall
+    let row_height = area.h() / Wall::ALL.len() as f32;
+    let row_y = |wall: Wall| area.top() - (wall.index() as f32 + 0.5) * row_height;
+    for wall in Wall::ALL.iter() {
+        let label = format!(
+            "{}: {}",
+            wall.name(),
+            ball_model.interaction(*wall).note_display
+        );
+        draw.text(&label)
+            .x_y(area.left() + 40.0, row_y(*wall) + row_height / 2.0 - 8.0)
+            .w(76.0)
+            .font_size(10)
+            .color(yellow);
+    }
+
+    // the hits (as long as the notes they trigger)
+    for hit in hits.iter() {
+        let start = rhythm::to_beats(hit.time, bpm);
+        if start > beats {
+            break;
+        }
+        let interaction = ball_model.hit_interaction(hit);
+        let length = interaction.length as f32 / 1000.0 * bpm / 60.0; // in beats
+        let w = (length.min(beats - start) / beats * area.w()).max(2.0);
+        let x = area.left() + start / beats * area.w() + w / 2.0;
+        let color = match hit.kind {
+            HitKind::Bounce | HitKind::Absorb => ball_model.ball.get_color(),
+            HitKind::Wrap | HitKind::Teleport => yellow,
+        };
+        let alpha = if "None" == interaction.note_display {
+            0.3 // silent hit
+        } else {
+            1.0
+        };
+        draw.rect()
+            .x_y(x, row_y(hit.wall) - 4.0)
+            .w_h(w, row_height * 0.5)
+            .color(nannou::color::rgba(
+                color.red,
+                color.green,
+                color.blue,
+                alpha,
+            ));
+    }
+}
+
+/// Draw the (possibly rotated and moved) walls of the arena.
+fn display_arena(arena: &Arena, draw: &Draw) {
+    let [tr, tl, bl, br] = arena.corners();
+    draw.quad()
+        .points(tr, tl, bl, br)
+        .color(nannou::color::rgba(
+            119.0 / 255.0,
+            129.0 / 255.0,
+            135.0 / 255.0,
+            0.3,
+        ));
+    for (start, end) in [(tr, tl), (tl, bl), (bl, br), (br, tr)].iter() {
+        draw.line()
+            .start(*start)
+            .end(*end)
+            .weight(2.0)
+            .color(nannou::color::rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.5));
+    }
+}
+
+/// Draw the borders of the pitch zones as tick marks on the walls.
+fn display_zones(arena: &Arena, ball_model: &BallModel, draw: &Draw) {
+    let (half_width, half_height) = (arena.width / 2.0, arena.height / 2.0);
+    for wall in Wall::ALL.iter() {
+        let distance = arena.distance(*wall);
+        for along in ball_model.interaction(*wall).zones.ticks() {
+            // same positions along the wall as the hits (see ball_events)
+            let on_wall = match wall {
+                Wall::Top => pt2(along * half_width, distance),
+                Wall::Bottom => pt2(along * half_width, -distance),
+                Wall::Right => pt2(distance, along * half_height),
+                Wall::Left => pt2(-distance, along * half_height),
+            };
+            draw.line()
+                .start(arena.to_world(on_wall))
+                .end(arena.to_world(on_wall - wall.normal() * 8.0))
+                .weight(2.0)
+                .color(nannou::color::rgba(255.0 / 255.0, 242.0 / 255.0, 0.0, 0.5));
+        }
+    }
+}
+
 /// Save the significant parts of the model (state).
 fn save_model(state: &SaveState) {
     // write out to file
     let fname = state.project_name.clone() + ".state";
-    let data = serde_json::to_string(&state).expect("could not serialize data");
+    let data = match serde_json::to_string(&state) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("could not serialize state data: {}", e);
+            return;
+        }
+    };
     let fpath = Path::new(&fname);
-    fs::write(fpath, data).expect("could not save data");
+    if let Err(e) = fs::write(fpath, data) {
+        eprintln!("could not write state data file {}: {}", fname, e);
+    }
 }
 
 /// Load the significant parts of the model (state).
 fn load_model(model: &mut Model) {
     // load state
     let fname = model.project_name.clone() + ".state";
-    let sdata = fs::read_to_string(fname).expect("could not read state data file");
-    let state: SaveState = serde_json::from_str(&sdata).expect("could not de-serialize data");
+    let sdata = match fs::read_to_string(&fname) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("could not read state data file {}: {}", fname, e);
+            return;
+        }
+    };
+    let state: SaveState = match serde_json::from_str(&sdata) {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("could not de-serialize state data file {}: {}", fname, e);
+            return;
+        }
+    };
     model.overwrite_state(state);
 }
@@ -0,0 +1,923 @@
+/// A module to schedule MIDI messages and keep track of the notes sent.
+use core::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use crate::drums;
+use crate::sink::Event;
+
+// controller number of "all notes off"
+const ALL_NOTES_OFF: u8 = 123;
+// controller number of the MPE timbre ("brightness")
+pub const TIMBRE: u8 = 74;
+// speed at full expression (corner of the velocity pad, pixels per update)
+const MAX_SPEED: f32 = 14.142136;
+// pitch bend range in semitones (General MIDI default)
+pub const BEND_RANGE: f32 = 2.0;
+// pitch bend range of MPE member channels in semitones
+pub const MPE_BEND_RANGE: f32 = 48.0;
+// 14 bit pitch bend of an unbent note
+pub const BEND_CENTER: u16 = 8192;
+
+// a struct to hold timing information and MIDI data
+// (to be put in a queue) ... all this to avoid lifetime
+// issues with the Model struct (when using wmidi)
+// *note* Eq, Order etc. are based on the timestamps, simultaneous
+// messages are ordered note offs first, then as they were queued
+#[derive(Clone, Debug)]
+pub struct TimedMidiMessage {
+    pub r#type: TimedMidiMessageType,
+    pub note: u8,            // MIDI note
+    pub channel: u8,         // MIDI channel (1 - 16)
+    pub velocity: u8,        // note velocity
+    pub timestamp: Duration, // when to trigger
+    pub port: String,        // MIDI out port to send to ("" = default output)
+    pub sequence: u64,       // position in the queue (set when queued)
+}
+
+// qualify the type of TimedMidiMessage (any channel voice message or a note tuning)
+#[derive(Clone, Copy, Debug, Hash, PartialEq)]
+pub enum TimedMidiMessageType {
+    NoteOn,
+    NoteOff,
+    PolyPressure(u8),        // aftertouch of the note
+    ControlChange(u8, u8),   // controller number, value
+    ProgramChange(u8),       // program number
+    ChannelPressure(u8),     // aftertouch of the whole channel
+    PitchBend(u16),          // 14 bit bend (8192 = none)
+    NoteTuning(u8, u8, u16), // MTS: key, semitone & 14 bit fraction it sounds at
+}
+
+impl PartialEq for TimedMidiMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TimedMidiMessage {}
+
+impl PartialOrd for TimedMidiMessage {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// the greatest message is processed first (by the BinaryHeap)
+impl Ord for TimedMidiMessage {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // earlier ones first
+        other
+            .timestamp
+            .cmp(&self.timestamp)
+            // a note off ends the previous hit before a simultaneous one starts
+            .then_with(|| self.is_note_off().cmp(&other.is_note_off()))
+            // first in, first out
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl TimedMidiMessage {
+    // Create note on & off MIDI message.
+    pub fn create_on_off(
+        port: &str,
+        chan: u8,
+        note: u8,
+        velocity: u8,
+        trigger: Duration,
+        length: Duration,
+    ) -> (TimedMidiMessage, TimedMidiMessage) {
+        let on = TimedMidiMessage {
+            r#type: TimedMidiMessageType::NoteOn,
+            note: note,
+            channel: chan,
+            velocity,
+            timestamp: trigger,
+            port: port.to_string(),
+            sequence: 0,
+        };
+        let off = TimedMidiMessage {
+            r#type: TimedMidiMessageType::NoteOff,
+            note: note,
+            channel: chan,
+            velocity: 0,
+            timestamp: trigger + length,
+            port: port.to_string(),
+            sequence: 0,
+        };
+        return (on, off);
+    }
+
+    // Create a (non note) MIDI message.
+    pub fn new(
+        r#type: TimedMidiMessageType,
+        port: &str,
+        chan: u8,
+        timestamp: Duration,
+    ) -> TimedMidiMessage {
+        TimedMidiMessage {
+            r#type,
+            note: 0,
+            channel: chan,
+            velocity: 0,
+            timestamp,
+            port: port.to_string(),
+            sequence: 0,
+        }
+    }
+
+    // Create a note off MIDI message to be sent right away.
+    pub fn note_off(port: &str, chan: u8, note: u8) -> TimedMidiMessage {
+        TimedMidiMessage {
+            r#type: TimedMidiMessageType::NoteOff,
+            note,
+            channel: chan,
+            velocity: 0,
+            timestamp: Duration::from_millis(0),
+            port: port.to_string(),
+            sequence: 0,
+        }
+    }
+
+    // Check if the message stops a note.
+    pub fn is_note_off(&self) -> bool {
+        self.r#type == TimedMidiMessageType::NoteOff
+    }
+
+    // Convert to MIDI message to send, every value is checked to be in range.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let c = self
+            .channel
+            .checked_sub(1) // channels are numbered 1 - 16
+            .and_then(|index| wmidi::Channel::from_index(index).ok())
+            .ok_or_else(|| format!("invalid MIDI channel {}", self.channel))?;
+        let u7 = |value: u8| {
+            wmidi::U7::try_from(value).map_err(|_| format!("MIDI value {} out of range", value))
+        };
+        let note = || {
+            wmidi::Note::try_from(self.note).map_err(|_| format!("invalid MIDI note {}", self.note))
+        };
+
+        let msg = match self.r#type {
+            // a real-time single note tuning change (SysEx) for all devices
+            TimedMidiMessageType::NoteTuning(key, semitone, fraction) => {
+                if key > 127 || semitone > 127 || fraction > 16383 {
+                    return Err(format!(
+                        "invalid tuning of key {}: {} + {}/16384",
+                        key, semitone, fraction
+                    ));
+                }
+                let (msb, lsb) = ((fraction >> 7) as u8, (fraction & 0x7F) as u8);
+                return Ok(vec![
+                    0xF0, 0x7F, 0x7F, 0x08, 0x02, 0x00, 0x01, key, semitone, msb, lsb, 0xF7,
+                ]);
+            }
+            TimedMidiMessageType::NoteOn => {
+                wmidi::MidiMessage::NoteOn(c, note()?, u7(self.velocity)?)
+            }
+            TimedMidiMessageType::NoteOff => {
+                wmidi::MidiMessage::NoteOff(c, note()?, u7(self.velocity)?)
+            }
+            TimedMidiMessageType::PolyPressure(pressure) => {
+                wmidi::MidiMessage::PolyphonicKeyPressure(c, note()?, u7(pressure)?)
+            }
+            TimedMidiMessageType::ControlChange(controller, value) => {
+                wmidi::MidiMessage::ControlChange(
+                    c,
+                    wmidi::ControlFunction(u7(controller)?),
+                    u7(value)?,
+                )
+            }
+            TimedMidiMessageType::ProgramChange(program) => {
+                wmidi::MidiMessage::ProgramChange(c, u7(program)?)
+            }
+            TimedMidiMessageType::ChannelPressure(pressure) => {
+                wmidi::MidiMessage::ChannelPressure(c, u7(pressure)?)
+            }
+            TimedMidiMessageType::PitchBend(bend) => wmidi::MidiMessage::PitchBendChange(
+                c,
+                wmidi::U14::try_from(bend)
+                    .map_err(|_| format!("pitch bend {} out of range", bend))?,
+            ),
+        };
+        let mut bytes = vec![0u8; msg.bytes_size()];
+        msg.copy_to_slice(bytes.as_mut_slice())
+            .map_err(|e| format!("{:?}", e))?;
+        Ok(bytes)
+    }
+}
+
+/// Messages waiting to be sent, the next due one comes out first.
+pub struct MidiQueue {
+    heap: BinaryHeap<TimedMidiMessage>,
+    next_sequence: u64, // given to the next queued message
+}
+
+impl MidiQueue {
+    // Create an empty queue.
+    pub fn new() -> MidiQueue {
+        MidiQueue {
+            heap: BinaryHeap::new(),
+            next_sequence: 0,
+        }
+    }
+
+    // Queue a message (after all queued ones due at the same time).
+    pub fn push(&mut self, mut msg: TimedMidiMessage) {
+        msg.sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.heap.push(msg);
+    }
+
+    // The next message due (if any).
+    pub fn peek(&self) -> Option<&TimedMidiMessage> {
+        self.heap.peek()
+    }
+
+    // Take the next message due (if any).
+    pub fn pop(&mut self) -> Option<TimedMidiMessage> {
+        self.heap.pop()
+    }
+
+    // Drop all queued messages.
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+
+    // Number of queued messages.
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+}
+
+/// Counts the note ons of every note (per port and channel) which are not
+/// yet followed by a note off. Overlapping hits of the same note keep it
+/// sounding until the note off of the last hit.
+pub struct ActiveNotes {
+    counts: HashMap<(String, u8, u8), u32>, // (port, channel, note) -> note ons
+}
+
+impl ActiveNotes {
+    // Create without any sounding notes.
+    pub fn new() -> ActiveNotes {
+        ActiveNotes {
+            counts: HashMap::new(),
+        }
+    }
+
+    // Register a note on.
+    pub fn note_on(&mut self, port: &str, channel: u8, note: u8) {
+        *self
+            .counts
+            .entry((port.to_string(), channel, note))
+            .or_insert(0) += 1;
+    }
+
+    // Register a note off, returns whether it should be sent (no other hit
+    // holds the note and the note was not released before).
+    pub fn note_off(&mut self, port: &str, channel: u8, note: u8) -> bool {
+        let key = (port.to_string(), channel, note);
+        match self.counts.get_mut(&key) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                false
+            }
+            Some(_) => {
+                self.counts.remove(&key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Check if a note is sounding.
+    pub fn is_sounding(&self, port: &str, channel: u8, note: u8) -> bool {
+        self.counts.contains_key(&(port.to_string(), channel, note))
+    }
+
+    // Forget all sounding notes, returns them as (port, channel, note).
+    pub fn release_all(&mut self) -> Vec<(String, u8, u8)> {
+        let mut notes: Vec<(String, u8, u8)> = self.counts.drain().map(|(key, _)| key).collect();
+        notes.sort();
+        notes
+    }
+
+    // Forget the sounding notes of a port, returns them as (channel, note).
+    pub fn release_port(&mut self, port: &str) -> Vec<(u8, u8)> {
+        let mut notes: Vec<(u8, u8)> = self
+            .counts
+            .keys()
+            .filter(|(p, _, _)| p == port)
+            .map(|(_, channel, note)| (*channel, *note))
+            .collect();
+        self.counts.retain(|(p, _, _), _| p != port);
+        notes.sort();
+        notes
+    }
+}
+
+/// The last pitch bend queued on every channel (per port), so that a note
+/// without a bend does not inherit the bend of a previous note.
+pub struct Bends {
+    values: HashMap<(String, u8), u16>, // (port, channel) -> 14 bit bend
+}
+
+impl Bends {
+    // Create with all channels centred.
+    pub fn new() -> Bends {
+        Bends {
+            values: HashMap::new(),
+        }
+    }
+
+    // Register a pitch bend queued on a channel.
+    pub fn bend(&mut self, port: &str, channel: u8, value: u16) {
+        self.values.insert((port.to_string(), channel), value);
+    }
+
+    // Register an unbent note on a channel, returns whether the bend has to
+    // be centred first (the last bend on the channel was not).
+    pub fn center(&mut self, port: &str, channel: u8) -> bool {
+        match self.values.remove(&(port.to_string(), channel)) {
+            Some(value) => value != BEND_CENTER,
+            None => false,
+        }
+    }
+}
+
+/// The keys retuned via MTS (per port), so that a note without a bend does
+/// not sound at the tuning of a previous note on the same key.
+pub struct Tunings {
+    keys: HashMap<(String, u8), TimedMidiMessageType>, // (port, key) -> last tuning
+}
+
+impl Tunings {
+    // Create with all keys at their own pitch.
+    pub fn new() -> Tunings {
+        Tunings {
+            keys: HashMap::new(),
+        }
+    }
+
+    // Register an MTS tuning queued for a key.
+    pub fn tune(&mut self, port: &str, key: u8, tuning: TimedMidiMessageType) {
+        self.keys.insert((port.to_string(), key), tuning);
+    }
+
+    // Register an unbent note on a key, returns whether the key has to be
+    // tuned back to its own pitch first (the last tuning of the key was not).
+    pub fn reset(&mut self, port: &str, key: u8) -> bool {
+        match self.keys.remove(&(port.to_string(), key)) {
+            Some(tuning) => tuning != note_tuning(key, key as f32),
+            None => false,
+        }
+    }
+
+    // Forget all tunings, returns the (port, key) of every key to be tuned
+    // back to its own pitch.
+    pub fn reset_all(&mut self) -> Vec<(String, u8)> {
+        let mut keys: Vec<(String, u8)> = self
+            .keys
+            .drain()
+            .filter(|((_, key), tuning)| *tuning != note_tuning(*key, *key as f32))
+            .map(|(port_key, _)| port_key)
+            .collect();
+        keys.sort();
+        keys
+    }
+}
+
+/// Bytes of an "all notes off" message for each of the 16 MIDI channels.
+pub fn all_notes_off() -> Vec<[u8; 3]> {
+    (0..16u8)
+        .map(|channel| [0xB0 | channel, ALL_NOTES_OFF, 0])
+        .collect()
+}
+
+/// What drives the per-note timbre and pressure of MPE notes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Expression {
+    Speed,    // speed of the ball
+    Distance, // distance of the ball from the centre of the arena
+}
+
+impl Expression {
+    pub const ALL: [Expression; 2] = [Expression::Speed, Expression::Distance];
+
+    // Human readable name of the expression.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Expression::Speed => "speed",
+            Expression::Distance => "distance",
+        }
+    }
+
+    // Expression of an event (0 - 1).
+    pub fn of(&self, event: &Event) -> f32 {
+        let x = match self {
+            Expression::Speed => event.speed / MAX_SPEED,
+            Expression::Distance => event.distance,
+        };
+        x.max(0.0).min(1.0)
+    }
+}
+
+/// MPE output in the lower zone: channel 1 is the master channel, every
+/// note gets a member channel (2 and up) of its own for its expression.
+pub struct Mpe {
+    pub active: bool,
+    pub members: u8, // number of member channels (1 - 15)
+    pub expression: Expression,
+    next: u8, // index of the member channel for the next note
+}
+
+impl Mpe {
+    // Create an inactive MPE output using all 15 member channels.
+    pub fn new() -> Mpe {
+        Mpe {
+            active: false,
+            members: 15,
+            expression: Expression::Speed,
+            next: 0,
+        }
+    }
+
+    // Member channel (2 - 16) for the next note, taking turns.
+    pub fn next_channel(&mut self) -> u8 {
+        let members = self.members.max(1).min(15);
+        let channel = 2 + self.next % members;
+        self.next = (self.next + 1) % members;
+        channel
+    }
+
+    // Pitch bend, timbre & pressure of an event's note, a note bent by some
+    // semitones (gliding along its wall or tuned) instead of its position.
+    pub fn expression(&self, event: &Event, bend: Option<f32>) -> (u16, u8, u8) {
+        let x = controller_value(self.expression.of(event));
+        let bend = match bend {
+            Some(semitones) => pitch_bend(semitones / MPE_BEND_RANGE),
+            None => pitch_bend(event.along),
+        };
+        (bend, x, x)
+    }
+
+    // MPE configuration message (RPN 6 on the master channel) announcing the zone.
+    pub fn configuration(&self) -> Vec<[u8; 3]> {
+        vec![
+            [0xB0, 101, 0], // RPN MSB
+            [0xB0, 100, 6], // RPN LSB: MPE configuration
+            [0xB0, 6, self.members.max(1).min(15)],
+        ]
+    }
+}
+
+/// How notes between the keys of a synthesizer in standard tuning are played.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TuningOutput {
+    PitchBend, // the nearest key, bent on a channel of its own
+    Mts,       // the key itself, tuned with a MIDI Tuning Standard message
+}
+
+impl TuningOutput {
+    pub const ALL: [TuningOutput; 2] = [TuningOutput::PitchBend, TuningOutput::Mts];
+
+    // Human readable name of the output.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TuningOutput::PitchBend => "pitch bend",
+            TuningOutput::Mts => "MTS",
+        }
+    }
+}
+
+/// Output of notes bent away from the keys (tuned or gliding), if not via MPE.
+pub struct Retune {
+    pub output: TuningOutput,
+    pub channels: u8, // channels bent notes take turns on (from the note's channel)
+    next: u8,         // index of the channel for the next note
+}
+
+impl Retune {
+    // Bend notes on their own channel only.
+    pub fn new() -> Retune {
+        Retune {
+            output: TuningOutput::PitchBend,
+            channels: 1,
+            next: 0,
+        }
+    }
+
+    // Channel for the next bent note, starting at the given one (1 - 16), so
+    // that the bend of a note does not change the notes still sounding. The
+    // channels above it take turns, skipping the drum channel and stopping
+    // at 16 instead of wrapping around to 1.
+    pub fn next_channel(&mut self, first: u8) -> u8 {
+        let first = first.max(1).min(16);
+        let channels: Vec<u8> = (first..=16)
+            .filter(|&c| c == first || c != drums::CHANNEL)
+            .take(self.channels.max(1) as usize)
+            .collect();
+        let channel = channels[self.next as usize % channels.len()];
+        self.next = ((self.next as usize + 1) % channels.len()) as u8;
+        channel
+    }
+}
+
+/// The nearest key & the bend (in semitones) for a fractional pitch.
+pub fn nearest_key(pitch: f32) -> (u8, f32) {
+    let key = pitch.round().max(0.0).min(127.0);
+    (key as u8, pitch - key)
+}
+
+/// MTS tuning of a key to a fractional pitch (semitone & 1/16384 semitones).
+pub fn note_tuning(key: u8, pitch: f32) -> TimedMidiMessageType {
+    let pitch = pitch.max(0.0).min(127.0 + 16383.0 / 16384.0);
+    let mut semitone = pitch.floor() as u8;
+    let mut fraction = ((pitch - pitch.floor()) * 16384.0).round() as u16;
+    if fraction > 16383 {
+        semitone += 1;
+        fraction = 0;
+    }
+    TimedMidiMessageType::NoteTuning(key, semitone.min(127), fraction)
+}
+
+/// 14 bit pitch bend for a position along a wall (-1 - 1, 0 = no bend).
+pub fn pitch_bend(along: f32) -> u16 {
+    (8192.0 + along.max(-1.0).min(1.0) * 8191.0).round() as u16
+}
+
+/// 7 bit controller value for a value of 0 - 1.
+pub fn controller_value(x: f32) -> u8 {
+    (x.max(0.0).min(1.0) * 127.0).round() as u8
+}
+
+/// Controller values written as "number=value" pairs (e.g. "74=100 1=20").
+pub fn parse_controllers(text: &str) -> Result<Vec<(u8, u8)>, String> {
+    let mut controllers = Vec::new();
+    for pair in text.split_whitespace() {
+        let mut parts = pair.splitn(2, '=');
+        let mut next = || {
+            parts
+                .next()
+                .and_then(|n| n.parse::<u8>().ok())
+                .filter(|n| *n < 128)
+        };
+        match (next(), next()) {
+            (Some(controller), Some(value)) => controllers.push((controller, value)),
+            _ => return Err(format!("expected controller=value (0 - 127): {}", pair)),
+        }
+    }
+    Ok(controllers)
+}
+
+/// Write controller values as "number=value" pairs.
+pub fn format_controllers(controllers: &[(u8, u8)]) -> String {
+    let pairs: Vec<String> = controllers
+        .iter()
+        .map(|(controller, value)| format!("{}={}", controller, value))
+        .collect();
+    pairs.join(" ")
+}
+
+#[cfg(test)]
+mod midi_test {
+    use super::*;
+    use crate::arena::{HitKind, Wall};
+    use crate::sink::Source;
+    use nannou::prelude::*;
+    use std::time::Duration;
+
+    fn event(along: f32, speed: f32, distance: f32) -> Event {
+        Event {
+            ball: 0,
+            source: Source::Wall(Wall::Top, HitKind::Bounce),
+            note: 60,
+            velocity: 64,
+            channel: 1,
+            port: String::new(),
+            program: None,
+            controllers: vec![],
+            length: 100,
+            position: pt2(0.0, 0.0),
+            along,
+            bend: None,
+            frequency: 261.6256,
+            distance,
+            speed,
+            time: Duration::from_millis(0),
+        }
+    }
+
+    fn message(r#type: TimedMidiMessageType, channel: u8) -> TimedMidiMessage {
+        TimedMidiMessage::new(r#type, "", channel, Duration::from_millis(0))
+    }
+
+    #[test]
+    fn to_bytes() {
+        let (on, off) = TimedMidiMessage::create_on_off(
+            "",
+            1,
+            60,
+            100,
+            Duration::from_millis(0),
+            Duration::from_millis(10),
+        );
+        assert_eq!(on.to_bytes(), Ok(vec![0x90, 60, 100]));
+        assert_eq!(off.to_bytes(), Ok(vec![0x80, 60, 0]));
+
+        let cc = message(TimedMidiMessageType::ControlChange(74, 127), 16);
+        assert_eq!(cc.to_bytes(), Ok(vec![0xBF, 74, 127]));
+        let program = message(TimedMidiMessageType::ProgramChange(5), 10);
+        assert_eq!(program.to_bytes(), Ok(vec![0xC9, 5]));
+        let pressure = message(TimedMidiMessageType::ChannelPressure(64), 2);
+        assert_eq!(pressure.to_bytes(), Ok(vec![0xD1, 64]));
+        let bend = message(TimedMidiMessageType::PitchBend(8192), 1);
+        assert_eq!(bend.to_bytes(), Ok(vec![0xE0, 0x00, 0x40]));
+
+        let mut poly = message(TimedMidiMessageType::PolyPressure(30), 1);
+        poly.note = 64;
+        assert_eq!(poly.to_bytes(), Ok(vec![0xA0, 64, 30]));
+
+        let tuning = message(TimedMidiMessageType::NoteTuning(61, 60, 8192), 1);
+        assert_eq!(
+            tuning.to_bytes(),
+            Ok(vec![
+                0xF0, 0x7F, 0x7F, 0x08, 0x02, 0x00, 0x01, 61, 60, 0x40, 0x00, 0xF7
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_messages() {
+        assert!(message(TimedMidiMessageType::ProgramChange(1), 0)
+            .to_bytes()
+            .is_err());
+        assert!(message(TimedMidiMessageType::ProgramChange(1), 17)
+            .to_bytes()
+            .is_err());
+        assert!(message(TimedMidiMessageType::ProgramChange(128), 1)
+            .to_bytes()
+            .is_err());
+        assert!(message(TimedMidiMessageType::PitchBend(16384), 1)
+            .to_bytes()
+            .is_err());
+        let (on, _) = TimedMidiMessage::create_on_off(
+            "",
+            1,
+            128, // no note
+            100,
+            Duration::from_millis(0),
+            Duration::from_millis(10),
+        );
+        assert!(on.to_bytes().is_err());
+        assert!(message(TimedMidiMessageType::NoteTuning(60, 128, 0), 1)
+            .to_bytes()
+            .is_err());
+    }
+
+    fn drain(queue: &mut MidiQueue) -> Vec<(u64, TimedMidiMessageType, u8)> {
+        let mut messages = vec![];
+        while let Some(msg) = queue.pop() {
+            messages.push((msg.timestamp.as_millis() as u64, msg.r#type, msg.note));
+        }
+        messages
+    }
+
+    #[test]
+    fn queue_by_time() {
+        let mut queue = MidiQueue::new();
+        let (on, off) = TimedMidiMessage::create_on_off(
+            "",
+            1,
+            60,
+            100,
+            Duration::from_millis(20),
+            Duration::from_millis(10),
+        );
+        queue.push(off);
+        queue.push(on);
+        queue.push(message(TimedMidiMessageType::ProgramChange(1), 1));
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.peek().unwrap().timestamp, Duration::from_millis(0));
+        assert_eq!(
+            drain(&mut queue),
+            vec![
+                (0, TimedMidiMessageType::ProgramChange(1), 0),
+                (20, TimedMidiMessageType::NoteOn, 60),
+                (30, TimedMidiMessageType::NoteOff, 60),
+            ]
+        );
+    }
+
+    #[test]
+    fn note_off_before_note_on() {
+        // the second hit starts when the first one ends
+        let mut queue = MidiQueue::new();
+        let (on1, off1) = TimedMidiMessage::create_on_off(
+            "",
+            1,
+            60,
+            100,
+            Duration::from_millis(0),
+            Duration::from_millis(10),
+        );
+        let (on2, off2) = TimedMidiMessage::create_on_off(
+            "",
+            1,
+            60,
+            100,
+            Duration::from_millis(10),
+            Duration::from_millis(10),
+        );
+        queue.push(on1);
+        queue.push(on2);
+        queue.push(off1);
+        queue.push(off2);
+        assert_eq!(
+            drain(&mut queue),
+            vec![
+                (0, TimedMidiMessageType::NoteOn, 60),
+                (10, TimedMidiMessageType::NoteOff, 60),
+                (10, TimedMidiMessageType::NoteOn, 60),
+                (20, TimedMidiMessageType::NoteOff, 60),
+            ]
+        );
+    }
+
+    #[test]
+    fn first_in_first_out() {
+        // e.g. MPE expression set up right before its note
+        let mut queue = MidiQueue::new();
+        let t = Duration::from_millis(5);
+        let types = [
+            TimedMidiMessageType::PitchBend(9000),
+            TimedMidiMessageType::ControlChange(74, 20),
+            TimedMidiMessageType::ChannelPressure(30),
+            TimedMidiMessageType::NoteOn,
+            TimedMidiMessageType::ProgramChange(3),
+            TimedMidiMessageType::NoteOff,
+        ];
+        for (i, r#type) in types.iter().enumerate() {
+            let mut msg = TimedMidiMessage::new(*r#type, "", 2, t);
+            msg.note = i as u8;
+            queue.push(msg);
+        }
+        let order: Vec<u8> = drain(&mut queue).iter().map(|m| m.2).collect();
+        assert_eq!(order, vec![5, 0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn single_note() {
+        let mut notes = ActiveNotes::new();
+        notes.note_on("", 1, 60);
+        assert!(notes.is_sounding("", 1, 60));
+        assert!(!notes.is_sounding("", 2, 60));
+        assert!(notes.note_off("", 1, 60));
+        assert!(!notes.is_sounding("", 1, 60));
+        assert!(!notes.note_off("", 1, 60)); // nothing left to stop
+    }
+
+    #[test]
+    fn overlapping_notes() {
+        let mut notes = ActiveNotes::new();
+        notes.note_on("", 1, 60);
+        notes.note_on("", 1, 60);
+        // the first note off must not cut the second hit short
+        assert!(!notes.note_off("", 1, 60));
+        assert!(notes.is_sounding("", 1, 60));
+        assert!(notes.note_off("", 1, 60));
+    }
+
+    #[test]
+    fn release() {
+        let mut notes = ActiveNotes::new();
+        notes.note_on("", 1, 60);
+        notes.note_on("synth", 2, 62);
+        notes.note_on("synth", 2, 62);
+        notes.note_on("synth", 3, 64);
+
+        assert_eq!(notes.release_port("synth"), vec![(2, 62), (3, 64)]);
+        assert!(!notes.note_off("synth", 2, 62)); // released already
+        assert_eq!(notes.release_all(), vec![(String::new(), 1, 60)]);
+        assert!(notes.release_all().is_empty());
+    }
+
+    #[test]
+    fn panic_messages() {
+        let messages = all_notes_off();
+        assert_eq!(messages.len(), 16);
+        assert_eq!(messages[0], [0xB0, 123, 0]);
+        assert_eq!(messages[15], [0xBF, 123, 0]);
+    }
+
+    #[test]
+    fn member_channels() {
+        let mut mpe = Mpe::new();
+        let channels: Vec<u8> = (0..16).map(|_| mpe.next_channel()).collect();
+        assert_eq!(channels[0], 2);
+        assert_eq!(channels[14], 16);
+        assert_eq!(channels[15], 2); // round robin
+
+        mpe.members = 3;
+        let channels: Vec<u8> = (0..4).map(|_| mpe.next_channel()).collect();
+        assert!(channels.iter().all(|c| *c >= 2 && *c <= 4));
+        assert_eq!(mpe.configuration()[2], [0xB0, 6, 3]);
+    }
+
+    #[test]
+    fn expression() {
+        assert_eq!(pitch_bend(0.0), 8192);
+        assert_eq!(pitch_bend(-1.0), 1);
+        assert_eq!(pitch_bend(1.0), 16383);
+        assert_eq!(pitch_bend(5.0), 16383);
+        assert_eq!(controller_value(0.5), 64);
+
+        let mut mpe = Mpe::new();
+        assert_eq!(
+            mpe.expression(&event(0.5, MAX_SPEED, 0.25), None),
+            (12288, 127, 127)
+        );
+        mpe.expression = Expression::Distance;
+        assert_eq!(
+            mpe.expression(&event(0.0, MAX_SPEED, 0.0), None),
+            (8192, 0, 0)
+        );
+        let bent = mpe.expression(&event(0.5, MAX_SPEED, 0.0), Some(-12.0));
+        assert_eq!(bent.0, pitch_bend(-0.25));
+    }
+
+    #[test]
+    fn retune() {
+        let mut retune = Retune::new();
+        assert_eq!(retune.next_channel(3), 3);
+        assert_eq!(retune.next_channel(3), 3);
+        retune.channels = 3;
+        let channels: Vec<u8> = (0..4).map(|_| retune.next_channel(15)).collect();
+        assert_eq!(channels, vec![15, 16, 15, 16]);
+        retune.channels = 4;
+        let channels: Vec<u8> = (0..5).map(|_| retune.next_channel(8)).collect();
+        assert_eq!(channels, vec![8, 9, 11, 12, 8]);
+
+        let (key, bend) = nearest_key(60.4);
+        assert_eq!(key, 60);
+        assert!((bend - 0.4).abs() < 1e-4);
+        let (key, bend) = nearest_key(66.7);
+        assert_eq!(key, 67);
+        assert!((bend + 0.3).abs() < 1e-4);
+        assert_eq!(
+            note_tuning(61, 60.5),
+            TimedMidiMessageType::NoteTuning(61, 60, 8192)
+        );
+        assert_eq!(
+            note_tuning(61, 60.99999),
+            TimedMidiMessageType::NoteTuning(61, 61, 0)
+        );
+    }
+
+    #[test]
+    fn controllers() {
+        assert_eq!(
+            parse_controllers(" 74=100  1=20 "),
+            Ok(vec![(74, 100), (1, 20)])
+        );
+        assert_eq!(parse_controllers(""), Ok(vec![]));
+        assert!(parse_controllers("74").is_err());
+        assert!(parse_controllers("74=128").is_err());
+        assert!(parse_controllers("cc=1").is_err());
+        assert_eq!(format_controllers(&[(74, 100), (1, 20)]), "74=100 1=20");
+    }
+
+    #[test]
+    fn bends() {
+        let mut bends = Bends::new();
+        assert!(!bends.center("", 1)); // never bent
+        bends.bend("", 1, pitch_bend(0.25));
+        bends.bend("synth", 2, BEND_CENTER);
+        assert!(!bends.center("", 2)); // other channel
+        assert!(!bends.center("synth", 1)); // other port
+        assert!(!bends.center("synth", 2)); // bent back already
+        assert!(bends.center("", 1));
+        assert!(!bends.center("", 1)); // centred now
+    }
+
+    #[test]
+    fn tunings() {
+        let mut tunings = Tunings::new();
+        assert!(!tunings.reset("", 61)); // never tuned
+        tunings.tune("", 61, note_tuning(61, 60.5));
+        tunings.tune("synth", 62, note_tuning(62, 62.0));
+        assert!(!tunings.reset("", 62)); // other key
+        assert!(!tunings.reset("synth", 61)); // other port
+        assert!(!tunings.reset("synth", 62)); // tuned back already
+        assert!(tunings.reset("", 61));
+        assert!(!tunings.reset("", 61)); // at its own pitch now
+
+        tunings.tune("synth", 64, note_tuning(64, 63.7));
+        tunings.tune("", 60, note_tuning(60, 60.2));
+        tunings.tune("", 65, note_tuning(65, 65.0));
+        let keys = vec![("".to_string(), 60), ("synth".to_string(), 64)];
+        assert_eq!(tunings.reset_all(), keys);
+        assert!(tunings.reset_all().is_empty());
+        assert!(!tunings.reset("", 60));
+    }
+}
@@ -31,3 +31,26 @@ pub const MIDINOTES: [(&str, u8); 25] = [
 pub const MIDICHANNELS: [&str; 16] = [
     "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15", "16",
 ];
+
+/// Name of a MIDI note as shown in the GUI ("None" for no note).
+pub fn note_name(note: u8) -> String {
+    match MIDINOTES.iter().find(|n| n.1 == note) {
+        Some(n) => n.0.to_string(),
+        None if note > 127 => "None".to_string(),
+        None => {
+            // named like the listed notes, e.g. "C5" or "A#1/Bb1"
+            let sharps = [
+                "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+            ];
+            let flats = [
+                "C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab", "A", "Bb", "B",
+            ];
+            let (class, octave) = ((note % 12) as usize, note as i32 / 12 - 1);
+            if sharps[class] == flats[class] {
+                format!("{}{}", sharps[class], octave)
+            } else {
+                format!("{}{}/{}{}", sharps[class], octave, flats[class], octave)
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+/// A module to model the forces acting on a ball.
+extern crate nannou;
+use nannou::prelude::*;
+
+use crate::attractor::Attractor;
+use crate::ball::Ball;
+
+/// Global forces: gravity, air drag and an optional "re-kick"
+/// to restore energy once a ball got too slow (under gravity: once it
+/// lies on a wall, not at the top of every bounce).
+pub struct Physics {
+    pub gravity: f32,          // strength of gravity (pixels per frame²)
+    pub gravity_angle: f32,    // direction of gravity in degrees (270 = down)
+    pub drag: f32,             // fraction of velocity lost per frame
+    pub rekick: bool,          // restore speed of slow balls
+    pub rekick_threshold: f32, // speed (pixels per frame) regarded as too slow
+    pub rekick_speed: f32,     // speed (pixels per frame) after a kick
+}
+
+impl Physics {
+    // Create physics without any forces (constant velocity).
+    pub fn new() -> Physics {
+        Physics {
+            gravity: 0.0,
+            gravity_angle: 270.0,
+            drag: 0.0,
+            rekick: false,
+            rekick_threshold: 0.5,
+            rekick_speed: 5.0,
+        }
+    }
+
+    // Acceleration due to gravity.
+    pub fn gravity_vector(&self) -> Point2 {
+        let a = self.gravity_angle.to_radians();
+        pt2(a.cos(), a.sin()) * self.gravity
+    }
+
+    // Sum of all accelerations acting on a ball at the given position.
+    pub fn acceleration(&self, p: Point2, attractors: &[Attractor]) -> Point2 {
+        let mut a = self.gravity_vector();
+        for attractor in attractors.iter() {
+            a = a + attractor.acceleration(p);
+        }
+        a
+    }
+
+    // Integrate the forces of one frame into the velocity of the ball
+    // (resting: the ball lies on a wall gravity presses it onto).
+    pub fn apply(&self, ball: &mut Ball, attractors: &[Attractor], resting: bool) {
+        let mut v = ball.get_velocity();
+        v = v + self.acceleration(ball.get_position(), attractors);
+        v = v * (1.0 - self.drag);
+
+        // a wall holds a resting ball against gravity
+        let moving = if resting {
+            v - self.gravity_vector()
+        } else {
+            v
+        };
+        let speed = (moving.x * moving.x + moving.y * moving.y).sqrt();
+        let dying = self.gravity <= 0.0 || resting; // slow at the top of a bounce is fine
+        if self.rekick && dying && speed < self.rekick_threshold {
+            v = if speed > 0.0 {
+                moving * (self.rekick_speed / speed)
+            } else {
+                // no direction left, kick against gravity
+                let a = (self.gravity_angle + 180.0).to_radians();
+                pt2(a.cos(), a.sin()) * self.rekick_speed
+            };
+        }
+        ball.set_velocity(v);
+    }
+}
+
+#[cfg(test)]
+mod physics_test {
+    use super::*;
+
+    #[test]
+    fn no_forces() {
+        let p = Physics::new();
+        let mut b = Ball::new();
+        b.set_velocity(pt2(1.5, -2.0));
+
+        p.apply(&mut b, &[], false);
+        assert_eq!(b.get_velocity(), pt2(1.5, -2.0));
+    }
+
+    #[test]
+    fn gravity() {
+        let mut p = Physics::new();
+        p.gravity = 0.5;
+        let g = p.gravity_vector();
+        assert!(g.x.abs() < 1e-6);
+        assert!((g.y + 0.5).abs() < 1e-6);
+
+        let mut b = Ball::new();
+        p.apply(&mut b, &[], false);
+        p.apply(&mut b, &[], false);
+        assert!((b.get_velocity().y + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn drag() {
+        let mut p = Physics::new();
+        p.drag = 0.1;
+        let mut b = Ball::new();
+        b.set_velocity(pt2(10.0, 0.0));
+
+        p.apply(&mut b, &[], false);
+        assert!((b.get_velocity().x - 9.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn attractors() {
+        let p = Physics::new();
+        let attractors = vec![
+            Attractor::new(pt2(100.0, 0.0)),
+            Attractor::new(pt2(0.0, 100.0)),
+        ];
+        let mut b = Ball::new();
+
+        p.apply(&mut b, &attractors, false);
+        let v = b.get_velocity();
+        assert!((v.x - 0.1).abs() < 1e-6);
+        assert!((v.y - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rekick() {
+        let mut p = Physics::new();
+        p.rekick = true;
+        let mut b = Ball::new();
+        b.set_velocity(pt2(0.0, 0.1));
+
+        p.apply(&mut b, &[], false);
+        assert!((b.get_velocity().y - p.rekick_speed).abs() < 1e-6);
+
+        // standing still: kick against gravity
+        b.set_velocity(pt2(0.0, 0.0));
+        p.apply(&mut b, &[], false);
+        assert!(b.get_velocity().x.abs() < 1e-5);
+        assert!((b.get_velocity().y - p.rekick_speed).abs() < 1e-5);
+    }
+
+    #[test]
+    fn rekick_under_gravity() {
+        let mut p = Physics::new();
+        p.rekick = true;
+        p.gravity = 0.5;
+        let mut b = Ball::new();
+
+        // at the top of a bounce the ball is slow, but falls back by itself
+        b.set_velocity(pt2(0.0, 0.2));
+        p.apply(&mut b, &[], false);
+        assert!((b.get_velocity().y + 0.3).abs() < 1e-6);
+
+        // lying on the floor it is kicked up again
+        b.set_velocity(pt2(0.0, 0.0));
+        p.apply(&mut b, &[], true);
+        assert!((b.get_velocity().y - p.rekick_speed).abs() < 1e-5);
+    }
+}
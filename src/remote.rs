@@ -0,0 +1,135 @@
+/// A module to receive remote control commands via OSC.
+use nannou_osc as osc;
+use std::io::Result;
+
+use crate::command::{Atom, Command};
+
+// all addresses start with this prefix, e.g. "/bq/transport/play"
+const ADDRESS_PREFIX: &str = "/bq/";
+
+/// Listens on a UDP port for OSC messages like "/bq/ball/0/velocity f f",
+/// "/bq/wall/top/note i", "/bq/transport/play" or "/bq/project/load s".
+pub struct OscRemote {
+    receiver: osc::Receiver,
+    port: u16,
+}
+
+impl OscRemote {
+    // Listen on the given host address (e.g. "127.0.0.1" for this computer
+    // only) and UDP port (0 = any free port).
+    pub fn new(host: &str, port: u16) -> Result<OscRemote> {
+        let receiver = osc::Receiver::bind_to((host, port))?;
+        let port = receiver.local_addr()?.port();
+        Ok(OscRemote { receiver, port })
+    }
+
+    // The UDP port listened on.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    // Collect the commands received since the last poll (without blocking).
+    pub fn poll(&self) -> Vec<std::result::Result<Command, String>> {
+        let mut commands = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(Some((packet, _))) => {
+                    for msg in packet.into_msgs() {
+                        commands.push(parse_message(&msg));
+                    }
+                }
+                Ok(None) => break, // nothing left
+                Err(e) => {
+                    commands.push(Err(format!("{}", e)));
+                    break;
+                }
+            }
+        }
+        commands
+    }
+}
+
+/// Turn an OSC message into a command: the address is split at
+/// the slashes, followed by the (int, float or string) arguments.
+pub fn parse_message(msg: &osc::Message) -> std::result::Result<Command, String> {
+    if !msg.addr.starts_with(ADDRESS_PREFIX) {
+        return Err(format!("unknown address {}", msg.addr));
+    }
+    let mut atoms: Vec<Atom> = msg.addr[ADDRESS_PREFIX.len()..]
+        .split('/')
+        .map(|part| Atom::Symbol(part.to_string()))
+        .collect();
+    if let Some(args) = &msg.args {
+        for arg in args.iter() {
+            let atom = match arg {
+                osc::Type::Int(i) => Atom::Float(*i as f32),
+                osc::Type::Float(f) => Atom::Float(*f),
+                osc::Type::Double(d) => Atom::Float(*d as f32),
+                osc::Type::String(s) => Atom::Symbol(s.clone()),
+                _ => return Err(format!("unsupported argument in {}", msg.addr)),
+            };
+            atoms.push(atom);
+        }
+    }
+    Command::parse(&atoms)
+}
+
+#[cfg(test)]
+mod remote_test {
+    use super::*;
+    use crate::arena::Wall;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    #[test]
+    fn parse() {
+        let msg = osc::Message {
+            addr: "/bq/ball/0/velocity".to_string(),
+            args: Some(vec![osc::Type::Float(1.5), osc::Type::Int(-2)]),
+        };
+        assert_eq!(parse_message(&msg), Ok(Command::BallVelocity(0, 1.5, -2.0)));
+
+        let msg = osc::Message {
+            addr: "/bq/project/load".to_string(),
+            args: Some(vec![osc::Type::String("groove".to_string())]),
+        };
+        assert_eq!(
+            parse_message(&msg),
+            Ok(Command::LoadProject("groove".to_string()))
+        );
+
+        let msg = osc::Message {
+            addr: "/other/transport/play".to_string(),
+            args: None,
+        };
+        assert!(parse_message(&msg).is_err());
+    }
+
+    #[test]
+    fn receive() {
+        let remote = OscRemote::new("127.0.0.1", 0).expect("could not listen");
+        let sender = osc::sender()
+            .expect("could not bind OSC sender")
+            .connect(format!("127.0.0.1:{}", remote.port()))
+            .expect("could not connect");
+        sender
+            .send(("/bq/transport/play", vec![]))
+            .expect("could not send");
+        sender
+            .send(("/bq/wall/top/note", vec![osc::Type::Int(60)]))
+            .expect("could not send");
+
+        let mut commands = vec![];
+        for _ in 0..100 {
+            commands.extend(remote.poll());
+            if commands.len() >= 2 {
+                break;
+            }
+            sleep(Duration::from_millis(10));
+        }
+        assert_eq!(
+            commands,
+            vec![Ok(Command::Play), Ok(Command::WallNote(Wall::Top, 60))]
+        );
+    }
+}
@@ -0,0 +1,146 @@
+/// A module to predict the rhythm a ball plays on the walls.
+extern crate nannou;
+use nannou::prelude::*;
+
+use crate::arena::{rotate, Arena, Hit, HitKind, Wall};
+use crate::ball::Ball;
+
+// the ball moves once per update
+pub const UPDATES_PER_SECOND: f32 = 60.0;
+
+/// Predict the next hits of a ball, up to count hits within horizon updates.
+/// Between two hits a ball moves on a straight line, so the time of each hit
+/// is calculated directly. Forces and moving walls are not taken into account,
+/// the walls are assumed to stay where they are now.
+pub fn predict(arena: &Arena, ball: &Ball, count: usize, horizon: f32) -> Vec<Hit> {
+    let mut ghost = Ball::new(); // moves instead of the real ball
+    ghost.set_radius(ball.get_radius());
+    ghost.set_position(ball.get_position());
+    ghost.set_velocity(ball.get_velocity());
+
+    let mut hits = arena.frozen().sweep(&mut ghost, horizon, count);
+    // an absorbed ball does not hit anything anymore
+    if let Some(i) = hits.iter().position(|hit| hit.kind == HitKind::Absorb) {
+        hits.truncate(i + 1);
+    }
+    hits
+}
+
+/// Find the velocity making a ball hit the left/right walls every beats_x
+/// and the top/bottom walls every beats_y beats (0 = never). The current
+/// direction of the ball is kept, only its speed along the axes changes.
+pub fn solve(arena: &Arena, ball: &Ball, beats_x: f32, beats_y: f32, bpm: f32) -> Point2 {
+    let radius = ball.get_radius();
+    // distances the ball travels between two hits (arena coordinates)
+    let dx = arena.distance(Wall::Left) + arena.distance(Wall::Right) - 2.0 * radius;
+    let dy = arena.distance(Wall::Top) + arena.distance(Wall::Bottom) - 2.0 * radius;
+    let speed = |d: f32, beats: f32| {
+        if beats > 0.0 && d > 0.0 {
+            d / to_updates(beats, bpm)
+        } else {
+            0.0
+        }
+    };
+
+    let v = rotate(ball.get_velocity(), -arena.get_angle());
+    let sign = |x: f32| if x < 0.0 { -1.0 } else { 1.0 };
+    let v_new = pt2(
+        sign(v.x) * speed(dx, beats_x),
+        sign(v.y) * speed(dy, beats_y),
+    );
+    rotate(v_new, arena.get_angle())
+}
+
+/// Convert a time in updates into beats.
+pub fn to_beats(updates: f32, bpm: f32) -> f32 {
+    updates / UPDATES_PER_SECOND * bpm / 60.0
+}
+
+/// Convert a time in beats into updates.
+pub fn to_updates(beats: f32, bpm: f32) -> f32 {
+    beats * 60.0 / bpm * UPDATES_PER_SECOND
+}
+
+#[cfg(test)]
+mod rhythm_test {
+    use super::*;
+    use crate::arena::{Wall, WallBehaviour};
+
+    fn ball_at(p: Point2, v: Point2) -> Ball {
+        let mut b = Ball::new();
+        b.set_radius(10.0);
+        b.set_position(p);
+        b.set_velocity(v);
+        b
+    }
+
+    #[test]
+    fn predict_hits() {
+        let a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        let b = ball_at(pt2(0.0, 0.0), pt2(4.0, 0.0));
+
+        let hits = predict(&a, &b, 3, 1000.0);
+        let walls: Vec<Wall> = hits.iter().map(|hit| hit.wall).collect();
+        assert_eq!(walls, vec![Wall::Right, Wall::Left, Wall::Right]);
+        assert!((hits[0].time - 22.5).abs() < 1e-4);
+        assert!((hits[1].time - 67.5).abs() < 1e-4);
+        assert!((hits[2].time - 112.5).abs() < 1e-4);
+
+        // the ball itself does not move
+        assert_eq!(b.get_position(), pt2(0.0, 0.0));
+        assert_eq!(b.get_velocity(), pt2(4.0, 0.0));
+    }
+
+    #[test]
+    fn horizon() {
+        let a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        let b = ball_at(pt2(0.0, 0.0), pt2(4.0, 0.0));
+        assert_eq!(predict(&a, &b, 10, 100.0).len(), 2);
+        assert!(predict(&a, &b, 10, 10.0).is_empty());
+        assert!(predict(&a, &ball_at(pt2(0.0, 0.0), pt2(0.0, 0.0)), 10, 1000.0).is_empty());
+    }
+
+    #[test]
+    fn absorbed() {
+        let mut a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        a.behaviours[Wall::Left.index()] = WallBehaviour::Absorb;
+        let b = ball_at(pt2(0.0, 0.0), pt2(4.0, 0.0));
+
+        let hits = predict(&a, &b, 10, 1000.0);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[1].kind, HitKind::Absorb);
+    }
+
+    #[test]
+    fn solve_velocity() {
+        let a = Arena::new(pt2(0.0, 0.0), 200.0, 200.0);
+        let mut b = ball_at(pt2(0.0, 0.0), pt2(-1.0, 1.0));
+
+        // 120 bpm: 90 updates for 180 px, 120 updates for 180 px
+        let v = solve(&a, &b, 3.0, 4.0, 120.0);
+        assert!((v.x + 2.0).abs() < 1e-5);
+        assert!((v.y - 1.5).abs() < 1e-5);
+        assert_eq!(solve(&a, &b, 0.0, 4.0, 120.0).x, 0.0);
+
+        // the predicted rhythm matches
+        b.set_velocity(v);
+        let hits = predict(&a, &b, 16, to_updates(12.0, 120.0) - 1.0);
+        let beats_of = |wall: Wall| -> Vec<f32> {
+            hits.iter()
+                .filter(|hit| hit.wall == wall)
+                .map(|hit| to_beats(hit.time, 120.0))
+                .collect()
+        };
+        let left = beats_of(Wall::Left);
+        let top = beats_of(Wall::Top);
+        assert!((left[1] - left[0] - 6.0).abs() < 1e-3);
+        assert!((top[1] - top[0] - 8.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn beats() {
+        // 120 bpm at 60 updates per second: 30 updates per beat
+        assert!((to_beats(45.0, 120.0) - 1.5).abs() < 1e-6);
+        assert!((to_updates(1.5, 120.0) - 45.0).abs() < 1e-4);
+    }
+}
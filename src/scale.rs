@@ -0,0 +1,221 @@
+/// A module to keep the notes played in a key.
+
+// names of the pitch classes, starting at C
+pub const ROOTS: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Pitch classes (semitones above the root) a note may use.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scale {
+    pub name: String,
+    pub steps: Vec<u8>, // sorted, starting at 0, all below 12
+}
+
+impl Scale {
+    // Create a scale from its intervals, e.g. [2, 2, 1, 2, 2, 2, 1] for major.
+    pub fn from_intervals(name: &str, intervals: &[u8]) -> Result<Scale, String> {
+        let mut steps = vec![0u8];
+        let mut pitch = 0u32;
+        for interval in intervals {
+            if *interval == 0 {
+                return Err("intervals must be at least one semitone".to_string());
+            }
+            pitch += *interval as u32;
+            if pitch > 12 {
+                return Err(format!("intervals exceed an octave ({} semitones)", pitch));
+            }
+            if pitch < 12 {
+                steps.push(pitch as u8);
+            }
+        }
+        Ok(Scale {
+            name: name.to_string(),
+            steps,
+        })
+    }
+
+    // Parse a custom interval list like "2 2 1 2 2 2 1" or "3,2,2,3,2".
+    pub fn parse(text: &str) -> Result<Scale, String> {
+        let intervals = text
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|word| !word.is_empty())
+            .map(|word| {
+                word.parse::<u8>()
+                    .map_err(|_| format!("invalid interval {}", word))
+            })
+            .collect::<Result<Vec<u8>, String>>()?;
+        if intervals.is_empty() {
+            return Err("no intervals given".to_string());
+        }
+        Scale::from_intervals("custom", &intervals)
+    }
+
+    // The intervals between the steps (e.g. to edit a scale as text).
+    pub fn intervals(&self) -> Vec<u8> {
+        let mut intervals: Vec<u8> = self.steps.windows(2).map(|w| w[1] - w[0]).collect();
+        intervals.push(12 - self.steps.last().copied().unwrap_or(0));
+        intervals
+    }
+}
+
+/// The scales to choose from.
+pub fn presets() -> Vec<Scale> {
+    let scale = |name: &str, intervals: &[u8]| Scale::from_intervals(name, intervals).unwrap();
+    vec![
+        scale("chromatic", &[1; 12]),
+        scale("major", &[2, 2, 1, 2, 2, 2, 1]),
+        scale("minor", &[2, 1, 2, 2, 1, 2, 2]),
+        scale("harmonic minor", &[2, 1, 2, 2, 1, 3, 1]),
+        scale("dorian", &[2, 1, 2, 2, 2, 1, 2]),
+        scale("phrygian", &[1, 2, 2, 2, 1, 2, 2]),
+        scale("lydian", &[2, 2, 2, 1, 2, 2, 1]),
+        scale("mixolydian", &[2, 2, 1, 2, 2, 1, 2]),
+        scale("locrian", &[1, 2, 2, 1, 2, 2, 2]),
+        scale("major pentatonic", &[2, 2, 3, 2, 3]),
+        scale("minor pentatonic", &[3, 2, 2, 3, 2]),
+        scale("blues", &[3, 2, 1, 1, 3, 2]),
+    ]
+}
+
+/// A root and a scale all played notes are moved into.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Key {
+    pub root: u8, // pitch class of the root (0 = C)
+    pub scale: Scale,
+}
+
+impl Key {
+    // Create the chromatic key (every note stays as it is).
+    pub fn new() -> Key {
+        Key {
+            root: 0,
+            scale: presets().remove(0),
+        }
+    }
+
+    // Name of the key, e.g. "D minor".
+    pub fn name(&self) -> String {
+        format!("{} {}", ROOTS[self.root as usize % 12], self.scale.name)
+    }
+
+    // Check if a note belongs to the key.
+    pub fn contains(&self, note: u8) -> bool {
+        let step = ((note as i32 - self.root as i32).rem_euclid(12)) as u8;
+        self.scale.steps.contains(&step)
+    }
+
+    // Move a note to the nearest one of the key (the lower one if two are
+    // equally near). Notes outside the MIDI range (e.g. 128 = none) stay.
+    pub fn quantize(&self, note: u8) -> u8 {
+        if note > 127 || self.contains(note) {
+            return note;
+        }
+        for distance in 1..12i32 {
+            for candidate in [note as i32 - distance, note as i32 + distance].iter() {
+                if *candidate >= 0 && *candidate <= 127 && self.contains(*candidate as u8) {
+                    return *candidate as u8;
+                }
+            }
+        }
+        note
+    }
+
+    // Move a note into the key and then the given number of steps of the
+    // scale up (as far as the MIDI range allows).
+    pub fn step_up(&self, note: u8, steps: usize) -> u8 {
+        let mut note = self.quantize(note);
+        if note > 127 {
+            return note;
+        }
+        for _ in 0..steps {
+            match (note + 1..=127).find(|n| self.contains(*n)) {
+                Some(next) => note = next,
+                None => break,
+            }
+        }
+        note
+    }
+
+    // Move a note chosen in a key with another root into this one: it is
+    // transposed by the (smallest) distance between the roots, then quantized.
+    pub fn transpose_from(&self, root: u8, note: u8) -> u8 {
+        if note > 127 {
+            return note;
+        }
+        let mut shift = (self.root as i32 - root as i32).rem_euclid(12);
+        if shift > 6 {
+            shift -= 12;
+        }
+        let moved = (note as i32 + shift).max(0).min(127) as u8;
+        self.quantize(moved)
+    }
+}
+
+#[cfg(test)]
+mod scale_test {
+    use super::*;
+
+    fn key(root: u8, name: &str) -> Key {
+        let scale = presets().into_iter().find(|s| s.name == name).unwrap();
+        Key { root, scale }
+    }
+
+    #[test]
+    fn scales() {
+        let major = key(0, "major").scale;
+        assert_eq!(major.steps, vec![0, 2, 4, 5, 7, 9, 11]);
+        assert_eq!(major.intervals(), vec![2, 2, 1, 2, 2, 2, 1]);
+        assert_eq!(key(0, "minor pentatonic").scale.steps, vec![0, 3, 5, 7, 10]);
+        assert_eq!(presets()[0].steps.len(), 12);
+    }
+
+    #[test]
+    fn custom() {
+        let s = Scale::parse("3, 4 5").unwrap();
+        assert_eq!(s.steps, vec![0, 3, 7]);
+        assert_eq!(s.name, "custom");
+        assert!(Scale::parse("").is_err());
+        assert!(Scale::parse("2 x").is_err());
+        assert!(Scale::parse("7 7").is_err()); // more than an octave
+        assert!(Scale::parse("0 12").is_err());
+    }
+
+    #[test]
+    fn quantize() {
+        let c_major = key(0, "major");
+        assert_eq!(c_major.quantize(60), 60); // C stays
+        assert_eq!(c_major.quantize(61), 60); // C# -> C (tie: down)
+        assert_eq!(c_major.quantize(66), 65); // F# -> F
+        assert_eq!(c_major.quantize(128), 128); // no note
+
+        let a_minor_pentatonic = key(9, "minor pentatonic"); // A C D E G
+        assert_eq!(a_minor_pentatonic.quantize(65), 64); // F -> E
+        assert_eq!(a_minor_pentatonic.quantize(71), 72); // B -> C (A is further)
+        assert_eq!(a_minor_pentatonic.quantize(0), 0); // C
+        assert_eq!(a_minor_pentatonic.quantize(1), 0);
+        assert!(Key::new().contains(61));
+        assert_eq!(a_minor_pentatonic.name(), "A minor pentatonic");
+    }
+
+    #[test]
+    fn transpose() {
+        let d_major = key(2, "major");
+        assert_eq!(d_major.transpose_from(0, 60), 62); // C -> D
+        assert_eq!(d_major.transpose_from(0, 64), 66); // E -> F#
+        assert_eq!(d_major.transpose_from(2, 61), 62); // same root: quantized only
+        let b_major = key(11, "major");
+        assert_eq!(b_major.transpose_from(0, 60), 59); // down a semitone
+        assert_eq!(b_major.transpose_from(0, 128), 128);
+    }
+
+    #[test]
+    fn steps() {
+        let c_major = key(0, "major");
+        assert_eq!(c_major.step_up(60, 0), 60);
+        assert_eq!(c_major.step_up(60, 2), 64); // C -> E
+        assert_eq!(c_major.step_up(61, 7), 72); // C# is C, an octave up
+        assert_eq!(c_major.step_up(126, 5), 127); // G9 is the highest note
+        assert_eq!(Key::new().step_up(60, 3), 63);
+    }
+}
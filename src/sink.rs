@@ -0,0 +1,235 @@
+/// A module to pass on what the balls do to the outputs (MIDI, OSC, pd, ...).
+extern crate nannou;
+use nannou::prelude::*;
+use std::io::Result;
+use std::time::Duration;
+
+use crate::arena::{HitKind, Wall};
+
+/// What triggered an event.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+    Wall(Wall, HitKind), // a ball reached a wall
+    Attractor(usize),    // a ball passed an attractor (index)
+}
+
+impl Source {
+    // Short description, e.g. "hit top" or "pass 2".
+    pub fn describe(&self) -> String {
+        match self {
+            Source::Wall(wall, HitKind::Bounce) => format!("hit {}", wall.name()),
+            Source::Wall(wall, HitKind::Wrap) => format!("wrap {}", wall.name()),
+            Source::Wall(wall, HitKind::Absorb) => format!("absorb {}", wall.name()),
+            Source::Wall(wall, HitKind::Teleport) => format!("teleport {}", wall.name()),
+            Source::Attractor(i) => format!("pass {}", i),
+        }
+    }
+}
+
+/// Something a ball did and the note it plays.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    pub ball: u64, // id of the ball (0 = main ball)
+    pub source: Source,
+    pub note: u8,                   // MIDI note
+    pub velocity: u8,               // MIDI velocity
+    pub channel: u8,                // MIDI channel
+    pub port: String,               // MIDI out port ("" = default output)
+    pub program: Option<u8>,        // MIDI program change before the note
+    pub controllers: Vec<(u8, u8)>, // MIDI controller values before the note
+    pub length: u64,                // note length in ms
+    pub position: Point2,           // where the ball is (arena coordinates)
+    pub along: f32,                 // where along the wall (-1 - 1, 0 = middle)
+    pub bend: Option<f32>,          // semitones away from the note (gliding or tuned)
+    pub frequency: f32,             // Hz (in the tuning)
+    pub distance: f32,              // distance from the centre (0 - 1, 1 = corner)
+    pub speed: f32,                 // speed of the ball (pixels per update)
+    pub time: Duration,             // when the event is due (since start)
+}
+
+/// An output events can be sent to.
+pub trait EventSink {
+    // Human readable name of the output.
+    fn name(&self) -> String;
+
+    // Send (or schedule) an event, now is the time since start.
+    fn send(&mut self, event: &Event, now: Duration) -> Result<()>;
+}
+
+/// Which balls are routed to an output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BallFilter {
+    All,
+    Main,    // only the main ball
+    Emitted, // only balls launched by emitters
+}
+
+impl BallFilter {
+    pub const ALL: [BallFilter; 3] = [BallFilter::All, BallFilter::Main, BallFilter::Emitted];
+
+    // Human readable name of the filter.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BallFilter::All => "all balls",
+            BallFilter::Main => "main ball",
+            BallFilter::Emitted => "emitted balls",
+        }
+    }
+
+    // Check if the ball with the given id passes the filter.
+    pub fn accepts(&self, ball: u64) -> bool {
+        match self {
+            BallFilter::All => true,
+            BallFilter::Main => ball == 0,
+            BallFilter::Emitted => ball != 0,
+        }
+    }
+}
+
+/// Which events are sent to an output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Route {
+    pub active: bool,      // send anything at all
+    pub walls: [bool; 4],  // events of the individual walls
+    pub attractors: bool,  // events of the attractors
+    pub balls: BallFilter, // balls to pass on
+}
+
+impl Route {
+    // Create an inactive route which passes on all events once activated.
+    pub fn new() -> Route {
+        Route {
+            active: false,
+            walls: [true; 4],
+            attractors: true,
+            balls: BallFilter::All,
+        }
+    }
+
+    // Check if an event should be sent to the output.
+    pub fn accepts(&self, event: &Event) -> bool {
+        let source = match event.source {
+            Source::Wall(wall, _) => self.walls[wall.index()],
+            Source::Attractor(_) => self.attractors,
+        };
+        self.active && source && self.balls.accepts(event.ball)
+    }
+}
+
+/// Print events to the console.
+pub struct LogSink;
+
+impl EventSink for LogSink {
+    fn name(&self) -> String {
+        "log".to_string()
+    }
+
+    fn send(&mut self, event: &Event, _now: Duration) -> Result<()> {
+        println!(
+            "{:.3} s: ball {} {} (note {}, velocity {}, channel {})",
+            event.time.as_secs_f32(),
+            event.ball,
+            event.source.describe(),
+            event.note,
+            event.velocity,
+            event.channel
+        );
+        Ok(())
+    }
+}
+
+/// Keep all events (to inspect them in tests).
+#[cfg(test)]
+pub struct CaptureSink {
+    pub events: Vec<Event>,
+}
+
+#[cfg(test)]
+impl CaptureSink {
+    // Create a sink without any events.
+    pub fn new() -> CaptureSink {
+        CaptureSink { events: vec![] }
+    }
+}
+
+#[cfg(test)]
+impl EventSink for CaptureSink {
+    fn name(&self) -> String {
+        "capture".to_string()
+    }
+
+    fn send(&mut self, event: &Event, _now: Duration) -> Result<()> {
+        self.events.push(event.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod sink_test {
+    use super::*;
+
+    fn event(ball: u64, source: Source) -> Event {
+        Event {
+            ball,
+            source,
+            note: 60,
+            velocity: 64,
+            channel: 1,
+            port: String::new(),
+            program: None,
+            controllers: vec![],
+            length: 100,
+            position: pt2(0.0, 0.0),
+            along: 0.0,
+            bend: None,
+            frequency: 261.6256,
+            distance: 0.0,
+            speed: 1.0,
+            time: Duration::from_millis(0),
+        }
+    }
+
+    #[test]
+    fn route() {
+        let top = event(0, Source::Wall(Wall::Top, HitKind::Bounce));
+        let left = event(3, Source::Wall(Wall::Left, HitKind::Wrap));
+        let pass = event(0, Source::Attractor(1));
+
+        let mut r = Route::new();
+        assert!(!r.accepts(&top));
+
+        r.active = true;
+        assert!(r.accepts(&top) && r.accepts(&left) && r.accepts(&pass));
+
+        r.walls[Wall::Left.index()] = false;
+        r.attractors = false;
+        assert!(r.accepts(&top));
+        assert!(!r.accepts(&left));
+        assert!(!r.accepts(&pass));
+
+        r.walls[Wall::Left.index()] = true;
+        r.balls = BallFilter::Main;
+        assert!(r.accepts(&top));
+        assert!(!r.accepts(&left));
+        r.balls = BallFilter::Emitted;
+        assert!(!r.accepts(&top));
+        assert!(r.accepts(&left));
+    }
+
+    #[test]
+    fn capture() {
+        let mut sink = CaptureSink::new();
+        let e = event(2, Source::Attractor(0));
+        sink.send(&e, Duration::from_millis(0)).unwrap();
+        assert_eq!(sink.events, vec![e]);
+    }
+
+    #[test]
+    fn describe() {
+        assert_eq!(
+            Source::Wall(Wall::Top, HitKind::Bounce).describe(),
+            "hit top"
+        );
+        assert_eq!(Source::Attractor(2).describe(), "pass 2");
+    }
+}
@@ -0,0 +1,421 @@
+/// A module to stream the state of the simulation as JSON via WebSocket.
+use serde::Serialize;
+use std::io::{ErrorKind, Result};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+use tungstenite::handshake::server::{NoCallback, ServerHandshake};
+use tungstenite::handshake::{HandshakeError, MidHandshake};
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{Message, WebSocket};
+
+use crate::arena::Hit;
+use crate::ball::Ball;
+use crate::command::{Atom, Command};
+use crate::sink::Event;
+
+// time a connecting client gets to complete the handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+// most clients in the middle of their handshake at the same time
+const MAX_HANDSHAKES: usize = 8;
+// most messages queued for a client not keeping up with reading (about a second of states)
+const MAX_SEND_QUEUE: usize = 64;
+
+// the server side of a handshake waiting for more data of the client
+type Handshake = MidHandshake<ServerHandshake<TcpStream, NoCallback>>;
+
+/// Where a ball is and where it is heading.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct BallState {
+    pub id: u64, // 0 = main ball
+    pub x: f32,
+    pub y: f32,
+    pub velocity_x: f32,
+    pub velocity_y: f32,
+    pub radius: f32,
+}
+
+impl BallState {
+    // Take the state of a ball with the given id.
+    pub fn new(id: u64, ball: &Ball) -> BallState {
+        let p = ball.get_position();
+        let v = ball.get_velocity();
+        BallState {
+            id,
+            x: p.x,
+            y: p.y,
+            velocity_x: v.x,
+            velocity_y: v.y,
+            radius: ball.get_radius(),
+        }
+    }
+}
+
+/// A ball reaching a wall during the last update.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct HitState {
+    pub ball: u64,
+    pub wall: String, // "top", "right", ...
+    pub kind: String, // "bounce", "wrap", ...
+}
+
+impl HitState {
+    // Describe a hit of the ball with the given id.
+    pub fn new(ball: u64, hit: &Hit) -> HitState {
+        HitState {
+            ball,
+            wall: hit.wall.name().to_string(),
+            kind: format!("{:?}", hit.kind).to_lowercase(),
+        }
+    }
+}
+
+/// A note played by a ball.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct NoteState {
+    pub ball: u64,
+    pub source: String, // e.g. "hit top" or "pass 2"
+    pub note: u8,
+    pub velocity: u8,
+    pub channel: u8,
+    pub length: u64, // ms
+    pub x: f32,
+    pub y: f32,
+    pub speed: f32,
+    pub time: f32, // seconds since start
+}
+
+impl NoteState {
+    // Describe the note of an event.
+    pub fn new(event: &Event) -> NoteState {
+        NoteState {
+            ball: event.ball,
+            source: event.source.describe(),
+            note: event.note,
+            velocity: event.velocity,
+            channel: event.channel,
+            length: event.length,
+            x: event.position.x,
+            y: event.position.y,
+            speed: event.speed,
+            time: event.time.as_secs_f32(),
+        }
+    }
+}
+
+/// What is sent to the clients, e.g. {"type": "note", "ball": 0, ...}.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Report {
+    State {
+        time: f32, // seconds since start
+        playing: bool,
+        balls: Vec<BallState>,
+        hits: Vec<HitState>,
+    },
+    Note(NoteState),
+}
+
+/// Turn a JSON command into a command: an array of words and numbers
+/// like ["wall", "top", "note", 60] or ["transport", "play"].
+pub fn parse_command(text: &str) -> std::result::Result<Command, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| format!("invalid JSON: {}", e))?;
+    let items = match value.as_array() {
+        Some(items) => items,
+        None => return Err(format!("expected an array: {}", text)),
+    };
+    let mut atoms = Vec::new();
+    for item in items {
+        let atom = match item {
+            serde_json::Value::Number(n) => match n.as_f64() {
+                Some(f) => Atom::Float(f as f32),
+                None => return Err(format!("invalid number {}", n)),
+            },
+            serde_json::Value::String(s) => Atom::Symbol(s.clone()),
+            _ => return Err(format!("unsupported value {}", item)),
+        };
+        atoms.push(atom);
+    }
+    Command::parse(&atoms)
+}
+
+/// A local WebSocket server sending reports to all connected clients
+/// (e.g. visualisers in a browser) and receiving their JSON commands.
+pub struct StateServer {
+    listener: TcpListener,
+    clients: Vec<WebSocket<TcpStream>>,
+    handshakes: Vec<(Handshake, Instant)>, // connecting clients & when they connected
+    port: u16,
+}
+
+impl StateServer {
+    // Listen on the given host address (e.g. "127.0.0.1" for this computer
+    // only) and TCP port (0 = any free port).
+    pub fn new(host: &str, port: u16) -> Result<StateServer> {
+        let listener = TcpListener::bind((host, port))?;
+        let port = listener.local_addr()?.port();
+        listener.set_nonblocking(true)?;
+        Ok(StateServer {
+            listener,
+            clients: vec![],
+            handshakes: vec![],
+            port,
+        })
+    }
+
+    // The TCP port listened on.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    // Number of connected clients.
+    pub fn clients(&self) -> usize {
+        self.clients.len()
+    }
+
+    // Accept new clients and collect the commands received since the
+    // last poll (without blocking). The handshakes of connecting clients
+    // go on with every poll until done or timed out.
+    pub fn poll(&mut self) -> Vec<std::result::Result<Command, String>> {
+        let mut commands = Vec::new();
+        for (handshake, started) in std::mem::replace(&mut self.handshakes, vec![]) {
+            let step = handshake.handshake();
+            self.upgrade(step, started, &mut commands);
+        }
+        loop {
+            match self.listener.accept() {
+                Ok((_, address)) if self.handshakes.len() >= MAX_HANDSHAKES => {
+                    commands.push(Err(format!(
+                        "too many connecting clients, refused {}",
+                        address
+                    )));
+                }
+                Ok((stream, _)) => match stream.set_nonblocking(true) {
+                    Ok(_) => {
+                        let config = WebSocketConfig {
+                            max_send_queue: Some(MAX_SEND_QUEUE),
+                            ..WebSocketConfig::default()
+                        };
+                        let step = tungstenite::server::accept_with_config(stream, Some(config));
+                        self.upgrade(step, Instant::now(), &mut commands);
+                    }
+                    Err(e) => commands.push(Err(format!("{}", e))),
+                },
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    commands.push(Err(format!("{}", e)));
+                    break;
+                }
+            }
+        }
+
+        let mut i = 0;
+        while i < self.clients.len() {
+            match self.clients[i].read_message() {
+                Ok(Message::Text(text)) => commands.push(parse_command(&text)),
+                Ok(_) => {} // pings are answered by tungstenite
+                Err(tungstenite::Error::Io(ref e)) if e.kind() == ErrorKind::WouldBlock => i += 1,
+                Err(_) => {
+                    self.clients.remove(i); // closed or broken
+                }
+            }
+        }
+        commands
+    }
+
+    // Send a report to all clients, dropping those gone away or not reading.
+    pub fn send(&mut self, report: &Report) {
+        if self.clients.is_empty() {
+            return;
+        }
+        let text = match serde_json::to_string(report) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("WebSocket error: could not serialize report: {}", e);
+                return;
+            }
+        };
+        let mut i = 0;
+        while i < self.clients.len() {
+            match self.clients[i].write_message(Message::Text(text.clone())) {
+                Ok(_) => i += 1,
+                // queued, sent along with the next message
+                Err(tungstenite::Error::Io(ref e)) if e.kind() == ErrorKind::WouldBlock => i += 1,
+                Err(tungstenite::Error::SendQueueFull(_)) => {
+                    self.clients.remove(i); // not reading
+                }
+                Err(_) => {
+                    self.clients.remove(i); // closed or broken
+                }
+            }
+        }
+    }
+
+    // Take the result of a step of a client's handshake: a new client,
+    // a handshake to go on with (unless timed out) or a failure.
+    fn upgrade(
+        &mut self,
+        step: std::result::Result<
+            WebSocket<TcpStream>,
+            HandshakeError<ServerHandshake<TcpStream, NoCallback>>,
+        >,
+        started: Instant,
+        commands: &mut Vec<std::result::Result<Command, String>>,
+    ) {
+        match step {
+            Ok(client) => self.clients.push(client),
+            Err(HandshakeError::Interrupted(handshake)) => {
+                if started.elapsed() < HANDSHAKE_TIMEOUT {
+                    self.handshakes.push((handshake, started));
+                } else {
+                    commands.push(Err("WebSocket handshake timed out".to_string()));
+                }
+            }
+            Err(HandshakeError::Failure(e)) => {
+                commands.push(Err(format!("WebSocket handshake failed: {}", e)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod stream_test {
+    use super::*;
+    use crate::arena::{HitKind, Wall};
+    use nannou::prelude::*;
+    use std::thread;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_command(r#"["wall", "top", "note", 60]"#),
+            Ok(Command::WallNote(Wall::Top, 60))
+        );
+        assert_eq!(
+            parse_command(r#"["ball", 0, "velocity", 2.5, -1]"#),
+            Ok(Command::BallVelocity(0, 2.5, -1.0))
+        );
+        assert!(parse_command(r#"{"transport": "play"}"#).is_err());
+        assert!(parse_command(r#"["transport", null]"#).is_err());
+        assert!(parse_command("transport play").is_err());
+    }
+
+    #[test]
+    fn json() {
+        let report = Report::State {
+            time: 1.5,
+            playing: true,
+            balls: vec![],
+            hits: vec![HitState::new(
+                2,
+                &Hit {
+                    wall: Wall::Left,
+                    kind: HitKind::Bounce,
+                    time: 0.5,
+                    position: pt2(-90.0, 0.0),
+                },
+            )],
+        };
+        let value: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&report).unwrap()).unwrap();
+        assert_eq!(value["type"], "state");
+        assert_eq!(value["hits"][0]["wall"], "left");
+        assert_eq!(value["hits"][0]["kind"], "bounce");
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut server = StateServer::new("127.0.0.1", 0).expect("could not listen");
+        let url = format!("ws://127.0.0.1:{}/", server.port());
+        let client = thread::spawn(move || {
+            let (mut socket, _) = tungstenite::connect(url).expect("could not connect");
+            socket
+                .write_message(Message::Text(r#"["transport", "stop"]"#.to_string()))
+                .unwrap();
+            socket.read_message().unwrap()
+        });
+
+        let mut commands = vec![];
+        for _ in 0..100 {
+            commands.extend(server.poll());
+            if !commands.is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(commands, vec![Ok(Command::Stop)]);
+        assert_eq!(server.clients(), 1);
+
+        server.send(&Report::State {
+            time: 0.0,
+            playing: false,
+            balls: vec![],
+            hits: vec![],
+        });
+        let reply = client.join().unwrap();
+        assert!(reply.to_text().unwrap().contains(r#""type":"state""#));
+    }
+
+    #[test]
+    fn silent_client() {
+        let mut server = StateServer::new("127.0.0.1", 0).expect("could not listen");
+        let _silent = TcpStream::connect(("127.0.0.1", server.port())).unwrap();
+        thread::sleep(Duration::from_millis(20));
+
+        // a client that never sends its upgrade request does not hold up the poll
+        let start = Instant::now();
+        assert!(server.poll().is_empty());
+        assert!(start.elapsed() < Duration::from_millis(100));
+        assert_eq!(server.clients(), 0);
+        assert_eq!(server.handshakes.len(), 1);
+
+        thread::sleep(HANDSHAKE_TIMEOUT);
+        assert_eq!(
+            server.poll(),
+            vec![Err("WebSocket handshake timed out".to_string())]
+        );
+        assert!(server.handshakes.is_empty());
+    }
+
+    #[test]
+    fn slow_client() {
+        let mut server = StateServer::new("127.0.0.1", 0).expect("could not listen");
+        let url = format!("ws://127.0.0.1:{}/", server.port());
+        let client = thread::spawn(move || {
+            let (socket, _) = tungstenite::connect(url).expect("could not connect");
+            thread::sleep(Duration::from_secs(2)); // connected, but never reading
+            drop(socket);
+        });
+        for _ in 0..100 {
+            server.poll();
+            if server.clients() > 0 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(server.clients(), 1);
+
+        let ball = BallState {
+            id: 0,
+            x: 0.0,
+            y: 0.0,
+            velocity_x: 0.0,
+            velocity_y: 0.0,
+            radius: 10.0,
+        };
+        let report = Report::State {
+            time: 0.0,
+            playing: true,
+            balls: vec![ball; 1000],
+            hits: vec![],
+        };
+        for _ in 0..1000 {
+            server.send(&report);
+            if server.clients() == 0 {
+                break;
+            }
+        }
+        assert_eq!(server.clients(), 0); // dropped instead of queueing without end
+        client.join().unwrap();
+    }
+}
@@ -0,0 +1,432 @@
+/// A module to tune notes to other than equal temperament (Scala files).
+use std::fs;
+use std::path::Path;
+
+// the note (A4) and frequency standard synthesizers are tuned to
+pub const REFERENCE_NOTE: u8 = 69;
+pub const REFERENCE_FREQUENCY: f32 = 440.0;
+// most degrees of a scale or keys of a mapping read from a file
+const MAX_SIZE: usize = 1024;
+
+/// Frequency of a (fractional) MIDI pitch in standard tuning.
+pub fn frequency(pitch: f32) -> f32 {
+    REFERENCE_FREQUENCY * 2f32.powf((pitch - REFERENCE_NOTE as f32) / 12.0)
+}
+
+/// (Fractional) MIDI pitch of a frequency in standard tuning.
+pub fn pitch(frequency: f32) -> f32 {
+    REFERENCE_NOTE as f32 + 12.0 * (frequency / REFERENCE_FREQUENCY).log2()
+}
+
+/// A scale as read from a Scala file (.scl).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scl {
+    pub description: String,
+    pub degrees: Vec<f64>, // cents above the root, the last is the period (e.g. 1200)
+}
+
+impl Scl {
+    // Twelve tone equal temperament.
+    pub fn equal() -> Scl {
+        Scl {
+            description: "12-TET".to_string(),
+            degrees: (1..=12).map(|i| i as f64 * 100.0).collect(),
+        }
+    }
+
+    // Read a scale: a description, the number of degrees and then the
+    // degrees, either in cents (e.g. "701.955") or as ratio (e.g. "3/2" or "2").
+    pub fn parse(text: &str) -> Result<Scl, String> {
+        let mut lines = text.lines().filter(|l| !l.starts_with('!'));
+        let description = lines.next().ok_or("no description")?.trim().to_string();
+        let count = first_word(lines.next())
+            .parse::<usize>()
+            .map_err(|_| "invalid number of degrees".to_string())?;
+        if count == 0 {
+            return Err("no degrees".to_string());
+        }
+        if count > MAX_SIZE {
+            return Err(format!("too many degrees {} (at most {})", count, MAX_SIZE));
+        }
+        let mut degrees = Vec::new();
+        for _ in 0..count {
+            let word = first_word(lines.next());
+            let cents = if word.contains('.') {
+                word.parse::<f64>().ok()
+            } else {
+                let mut parts = word.splitn(2, '/');
+                let numerator = parts.next().and_then(|n| n.parse::<f64>().ok());
+                let denominator = match parts.next() {
+                    Some(d) => d.parse::<f64>().ok(),
+                    None => Some(1.0),
+                };
+                match (numerator, denominator) {
+                    (Some(n), Some(d)) if n > 0.0 && d > 0.0 => Some(1200.0 * (n / d).log2()),
+                    _ => None,
+                }
+            };
+            match cents {
+                Some(c) => degrees.push(c),
+                None => return Err(format!("invalid degree {:?}", word)),
+            }
+        }
+        if *degrees.last().unwrap() <= 0.0 {
+            return Err("the period must be above the root".to_string());
+        }
+        Ok(Scl {
+            description,
+            degrees,
+        })
+    }
+
+    // Cents of any degree above (or below, if negative) the root.
+    pub fn cents(&self, degree: i32) -> f64 {
+        let n = self.degrees.len() as i32;
+        let period = self.degrees[self.degrees.len() - 1];
+        let (periods, step) = (degree.div_euclid(n), degree.rem_euclid(n));
+        let within = if step == 0 {
+            0.0
+        } else {
+            self.degrees[step as usize - 1]
+        };
+        periods as f64 * period + within
+    }
+}
+
+/// A keyboard mapping as read from a Scala file (.kbm).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kbm {
+    pub first_note: u8,            // lowest note to play
+    pub last_note: u8,             // highest note to play
+    pub middle_note: u8,           // note playing the root of the scale
+    pub reference_note: u8,        // note tuned to the reference frequency
+    pub reference_frequency: f32,  // Hz
+    pub octave_degree: i32,        // degree repeating the mapping (0 = scale size)
+    pub mapping: Vec<Option<i32>>, // degrees of the repeating keys (none = one key per degree)
+}
+
+impl Kbm {
+    // Every key plays the next degree, the root on C4, A4 = 440 Hz.
+    pub fn standard() -> Kbm {
+        Kbm {
+            first_note: 0,
+            last_note: 127,
+            middle_note: 60,
+            reference_note: REFERENCE_NOTE,
+            reference_frequency: REFERENCE_FREQUENCY,
+            octave_degree: 0,
+            mapping: vec![],
+        }
+    }
+
+    // Read a keyboard mapping: size of the mapping, first & last note, middle
+    // note, reference note & frequency, octave degree and then the degree of
+    // every key of the mapping ("x" = key not played).
+    pub fn parse(text: &str) -> Result<Kbm, String> {
+        let mut lines = text.lines().filter(|l| !l.starts_with('!'));
+        let mut number = |what: &str| -> Result<f64, String> {
+            first_word(lines.next())
+                .parse::<f64>()
+                .map_err(|_| format!("invalid {}", what))
+        };
+        let size = number("map size")? as usize;
+        if size > MAX_SIZE {
+            return Err(format!(
+                "map size {} too large (at most {})",
+                size, MAX_SIZE
+            ));
+        }
+        let note = |n: f64, what: &str| -> Result<u8, String> {
+            if n >= 0.0 && n <= 127.0 {
+                Ok(n as u8)
+            } else {
+                Err(format!("invalid {} {}", what, n))
+            }
+        };
+        let first_note = note(number("first note")?, "first note")?;
+        let last_note = note(number("last note")?, "last note")?;
+        let middle_note = note(number("middle note")?, "middle note")?;
+        let reference_note = note(number("reference note")?, "reference note")?;
+        let reference_frequency = number("reference frequency")? as f32;
+        if !reference_frequency.is_finite() || reference_frequency <= 0.0 {
+            return Err("the reference frequency must be above 0 Hz".to_string());
+        }
+        let octave_degree = number("octave degree")? as i32;
+        let mut mapping = Vec::new();
+        for _ in 0..size {
+            // missing keys at the end are not played
+            let word = first_word(lines.next());
+            mapping.push(word.parse::<i32>().ok());
+        }
+        Ok(Kbm {
+            first_note,
+            last_note,
+            middle_note,
+            reference_note,
+            reference_frequency,
+            octave_degree,
+            mapping,
+        })
+    }
+
+    // Degree of the scale (with the given number of degrees) a note plays.
+    pub fn degree(&self, note: u8, scale_size: usize) -> Option<i32> {
+        if note < self.first_note || note > self.last_note {
+            return None;
+        }
+        let offset = note as i32 - self.middle_note as i32;
+        if self.mapping.is_empty() {
+            return Some(offset);
+        }
+        let size = self.mapping.len() as i32;
+        let octave = if self.octave_degree > 0 {
+            self.octave_degree
+        } else {
+            scale_size as i32
+        };
+        self.mapping[offset.rem_euclid(size) as usize]
+            .map(|degree| offset.div_euclid(size) * octave + degree)
+    }
+}
+
+/// A scale on a keyboard.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tuning {
+    pub name: String,
+    pub scale: Scl,
+    pub keyboard: Kbm,
+}
+
+impl Tuning {
+    // Put a scale on a keyboard (the reference note has to be played).
+    pub fn new(name: &str, scale: Scl, keyboard: Kbm) -> Result<Tuning, String> {
+        if keyboard
+            .degree(keyboard.reference_note, scale.degrees.len())
+            .is_none()
+        {
+            return Err(format!(
+                "reference note {} is not mapped",
+                keyboard.reference_note
+            ));
+        }
+        Ok(Tuning {
+            name: name.to_string(),
+            scale,
+            keyboard,
+        })
+    }
+
+    // Twelve tone equal temperament, A4 = 440 Hz.
+    pub fn standard() -> Tuning {
+        Tuning {
+            name: "12-TET".to_string(),
+            scale: Scl::equal(),
+            keyboard: Kbm::standard(),
+        }
+    }
+
+    // Read the files named in a text like "just.scl" or "just.scl just.kbm"
+    // (no files = standard tuning).
+    pub fn load(files: &str) -> Result<Tuning, String> {
+        let mut scale = None;
+        let mut keyboard = Kbm::standard();
+        for file in files.split_whitespace() {
+            let path = Path::new(file);
+            let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", file, e))?;
+            if path.extension().map_or(false, |ext| ext == "kbm") {
+                keyboard = Kbm::parse(&text).map_err(|e| format!("{}: {}", file, e))?;
+            } else {
+                scale = Some(Scl::parse(&text).map_err(|e| format!("{}: {}", file, e))?);
+            }
+        }
+        match scale {
+            Some(scale) => Tuning::new(files.trim(), scale, keyboard),
+            None if files.trim().is_empty() => Ok(Tuning::standard()),
+            None => Err("no scale (.scl) given".to_string()),
+        }
+    }
+
+    // Tune the reference note (usually A4) to another frequency (Hz).
+    pub fn set_reference(&mut self, frequency: f32) {
+        if frequency.is_finite() && frequency > 0.0 {
+            self.keyboard.reference_frequency = frequency;
+        }
+    }
+
+    // Check if notes sound as on a synthesizer in standard tuning.
+    pub fn is_standard(&self) -> bool {
+        self.scale == Scl::equal() && self.keyboard == Kbm::standard()
+    }
+
+    // Frequency of a note (none if the keyboard does not play it).
+    pub fn frequency(&self, note: u8) -> Option<f32> {
+        let size = self.scale.degrees.len();
+        let degree = self.keyboard.degree(note, size)?;
+        let reference = self.keyboard.degree(self.keyboard.reference_note, size)?;
+        let cents = self.scale.cents(degree) - self.scale.cents(reference);
+        Some(self.keyboard.reference_frequency * 2f64.powf(cents / 1200.0) as f32)
+    }
+
+    // Frequency of a note bent by some semitones and how far (in semitones)
+    // a synthesizer in standard tuning has to bend the note to play it
+    // (none if it does not need to). Notes not played give none at all.
+    pub fn tune(&self, note: u8, bend: Option<f32>) -> Option<(f32, Option<f32>)> {
+        let frequency = self.frequency(note)? * 2f32.powf(bend.unwrap_or(0.0) / 12.0);
+        if self.is_standard() && bend.is_none() {
+            return Some((frequency, None));
+        }
+        Some((frequency, Some(pitch(frequency) - note as f32)))
+    }
+}
+
+// First word of a line (values may be followed by comments).
+fn first_word(line: Option<&str>) -> &str {
+    line.and_then(|l| l.split_whitespace().next()).unwrap_or("")
+}
+
+#[cfg(test)]
+mod tuning_test {
+    use super::*;
+
+    const JUST: &str = "! just.scl
+!
+5-limit just intonation
+ 12
+!
+ 16/15
+ 9/8
+ 6/5
+ 5/4
+ 4/3
+ 45/32
+ 3/2
+ 8/5
+ 5/3
+ 9/5
+ 15/8
+ 2/1
+";
+
+    const WHITE_KEYS: &str = "! white keys play a 7 note scale
+12
+0
+127
+60
+69
+440.0
+7
+0
+x
+1
+x
+2
+3
+x
+4
+x
+5
+x
+6
+";
+
+    const SEVEN_EDO: &str = "7-EDO
+7
+171.4286
+342.8571
+514.2857
+685.7143
+857.1429
+1028.5714
+1200.0
+";
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn standard() {
+        let tuning = Tuning::standard();
+        assert!(tuning.is_standard());
+        assert!(close(tuning.frequency(69).unwrap(), 440.0));
+        assert!(close(tuning.frequency(57).unwrap(), 220.0));
+        assert!(close(tuning.frequency(60).unwrap(), frequency(60.0)));
+        assert_eq!(tuning.tune(60, None).unwrap().1, None);
+        assert!(close(pitch(frequency(61.5)), 61.5));
+    }
+
+    #[test]
+    fn reference() {
+        let mut tuning = Tuning::standard();
+        tuning.set_reference(432.0);
+        assert!(!tuning.is_standard());
+        assert!(close(tuning.frequency(69).unwrap(), 432.0));
+        assert!(close(tuning.frequency(81).unwrap(), 864.0));
+        let (f, bend) = tuning.tune(69, None).unwrap();
+        assert!(close(f, 432.0));
+        assert!((bend.unwrap() - 12.0 * (432.0f32 / 440.0).log2()).abs() < 0.0001);
+
+        tuning.set_reference(0.0); // ignored
+        assert!(close(tuning.frequency(69).unwrap(), 432.0));
+    }
+
+    #[test]
+    fn scl() {
+        let scale = Scl::parse(JUST).unwrap();
+        assert_eq!(scale.description, "5-limit just intonation");
+        assert_eq!(scale.degrees.len(), 12);
+        assert!((scale.cents(7) - 701.955).abs() < 0.001);
+        assert!((scale.cents(12) - 1200.0).abs() < 0.001);
+        assert!((scale.cents(-5) + 1200.0 - 701.955).abs() < 0.001);
+
+        assert!(Scl::parse("! nothing\n").is_err());
+        assert!(Scl::parse("x\n2\n100.0\n").is_err()); // too few degrees
+        assert!(Scl::parse("x\n1\nabc\n").is_err());
+        assert!(Scl::parse("x\n18446744073709551615\n100.0\n").is_err()); // absurd size
+        assert_eq!(
+            Scl::parse("x\n1\n2 octave\n").unwrap().degrees,
+            vec![1200.0]
+        );
+    }
+
+    #[test]
+    fn just_intonation() {
+        let tuning = Tuning::new("just", Scl::parse(JUST).unwrap(), Kbm::standard()).unwrap();
+        assert!(!tuning.is_standard());
+        // A4 = 440 Hz is the major sixth (5/3) above C4
+        assert!(close(tuning.frequency(60).unwrap(), 264.0));
+        assert!(close(tuning.frequency(67).unwrap(), 396.0)); // fifth: 3/2
+        assert!(close(tuning.frequency(72).unwrap(), 528.0));
+
+        // the just fifth is about 2 cents above the equal one
+        let (f, bend) = tuning.tune(67, None).unwrap();
+        assert!(close(f, 396.0));
+        assert!((bend.unwrap() - (pitch(396.0) - 67.0)).abs() < 0.0001);
+    }
+
+    #[test]
+    fn kbm() {
+        let keyboard = Kbm::parse(WHITE_KEYS).unwrap();
+        assert_eq!(keyboard.mapping.len(), 12);
+        assert_eq!(keyboard.degree(60, 7), Some(0));
+        assert_eq!(keyboard.degree(61, 7), None); // black key
+        assert_eq!(keyboard.degree(62, 7), Some(1));
+        assert_eq!(keyboard.degree(72, 7), Some(7)); // an octave up
+        assert_eq!(keyboard.degree(59, 7), Some(-1));
+
+        // 7 equal steps per octave on the white keys
+        let tuning = Tuning::new("7-EDO", Scl::parse(SEVEN_EDO).unwrap(), keyboard).unwrap();
+        assert!(close(tuning.frequency(69).unwrap(), 440.0));
+        assert!(close(tuning.frequency(81).unwrap(), 880.0));
+        assert_eq!(tuning.frequency(70), None);
+        assert_eq!(tuning.tune(70, None), None);
+
+        assert!(Kbm::parse("0\n0\n127\n60\n200\n440\n0\n").is_err()); // note out of range
+        assert!(Kbm::parse("1e18\n0\n127\n60\n69\n440\n0\n").is_err()); // absurd size
+        assert!(Kbm::parse("0\n0\n127\n60\n69\n0\n0\n").is_err());
+        assert!(Kbm::parse("0\n0\n127\n60\n69\nnan\n0\n").is_err());
+        assert!(Kbm::parse("0\n0\n127\n60\n69\ninf\n0\n").is_err());
+        let unmapped = Kbm::parse("2\n0\n127\n60\n61\n440\n1\n0\nx\n").unwrap();
+        assert!(Tuning::new("x", Scl::equal(), unmapped).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+/// A module to divide a wall into zones of different pitch, like a keyboard.
+use serde::{Deserialize, Serialize};
+
+use crate::scale::Key;
+
+// largest number of zones (or semitones to glide) along a wall
+pub const MAX_ZONES: u8 = 48;
+
+/// How the place a ball hits a wall changes the note of the wall
+/// (from the left / bottom end of the wall to the right / top end).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Zones {
+    Off,       // the note of the wall wherever it is hit
+    Steps(u8), // zones, each a step of the key above the previous one
+    Glide(u8), // rising continuously over the semitones (fine tuned by pitch bend)
+}
+
+impl Zones {
+    pub const MODES: [&'static str; 3] = ["fixed", "zones", "glide"];
+
+    // Create zones of a mode (index into MODES) with a number of zones or semitones.
+    pub fn new(mode: usize, count: u8) -> Zones {
+        let count = count.min(MAX_ZONES);
+        match mode {
+            1 if count > 1 => Zones::Steps(count),
+            2 if count > 0 => Zones::Glide(count),
+            _ => Zones::Off,
+        }
+    }
+
+    // Position of the mode in MODES.
+    pub fn mode(&self) -> usize {
+        match self {
+            Zones::Off => 0,
+            Zones::Steps(_) => 1,
+            Zones::Glide(_) => 2,
+        }
+    }
+
+    // Number of zones or semitones (0 = off).
+    pub fn count(&self) -> u8 {
+        match self {
+            Zones::Off => 0,
+            Zones::Steps(n) | Zones::Glide(n) => *n,
+        }
+    }
+
+    // Human readable description, e.g. "8 zones".
+    pub fn name(&self) -> String {
+        match self {
+            Zones::Off => "fixed".to_string(),
+            Zones::Steps(n) => format!("{} zones", n),
+            Zones::Glide(n) => format!("glide {} st", n),
+        }
+    }
+
+    // The zone a position along the wall (-1 - 1) is in.
+    pub fn zone(&self, along: f32) -> usize {
+        let n = match self {
+            Zones::Steps(n) => *n as usize,
+            _ => return 0,
+        };
+        let i = ((along.max(-1.0).min(1.0) + 1.0) / 2.0 * n as f32) as usize;
+        i.min(n - 1)
+    }
+
+    // Note (within the key) and fine tuning in semitones (for a glide) played
+    // when a wall with the given note is hit at a position along it (-1 - 1).
+    pub fn pitch(&self, note: u8, along: f32, key: &Key) -> (u8, Option<f32>) {
+        match self {
+            Zones::Off => (key.quantize(note), None),
+            Zones::Steps(_) => (key.step_up(note, self.zone(along)), None),
+            Zones::Glide(n) if note <= 127 => {
+                let pitch = note as f32 + *n as f32 * (along.max(-1.0).min(1.0) + 1.0) / 2.0;
+                let pitch = pitch.min(127.0); // no bend beyond the highest note
+                let nearest = pitch.round();
+                (nearest as u8, Some(pitch - nearest))
+            }
+            Zones::Glide(_) => (note, None),
+        }
+    }
+
+    // Borders between the zones (positions along the wall, -1 - 1),
+    // for a glide one per semitone.
+    pub fn ticks(&self) -> Vec<f32> {
+        let n = self.count() as usize;
+        (1..n).map(|i| i as f32 / n as f32 * 2.0 - 1.0).collect()
+    }
+}
+
+#[cfg(test)]
+mod zone_test {
+    use super::*;
+    use crate::scale::{presets, Key};
+
+    fn c_major() -> Key {
+        Key {
+            root: 0,
+            scale: presets().into_iter().find(|s| s.name == "major").unwrap(),
+        }
+    }
+
+    #[test]
+    fn modes() {
+        assert_eq!(Zones::new(1, 8), Zones::Steps(8));
+        assert_eq!(Zones::new(1, 1), Zones::Off); // a single zone is the whole wall
+        assert_eq!(Zones::new(2, 100), Zones::Glide(MAX_ZONES));
+        assert_eq!(Zones::new(0, 8), Zones::Off);
+        assert_eq!(Zones::Glide(12).mode(), 2);
+        assert_eq!(Zones::Steps(4).name(), "4 zones");
+    }
+
+    #[test]
+    fn zones() {
+        let zones = Zones::Steps(4);
+        assert_eq!(zones.zone(-1.0), 0);
+        assert_eq!(zones.zone(-0.4), 1);
+        assert_eq!(zones.zone(0.1), 2);
+        assert_eq!(zones.zone(1.0), 3); // the end belongs to the last zone
+        assert_eq!(zones.ticks(), vec![-0.5, 0.0, 0.5]);
+        assert!(Zones::Off.ticks().is_empty());
+    }
+
+    #[test]
+    fn pitch() {
+        let key = c_major();
+        assert_eq!(Zones::Off.pitch(61, 0.5, &key), (60, None));
+        assert_eq!(Zones::Steps(8).pitch(60, -1.0, &key), (60, None));
+        assert_eq!(Zones::Steps(8).pitch(60, 0.1, &key), (67, None)); // 5th zone: G
+        assert_eq!(Zones::Steps(8).pitch(60, 1.0, &key), (72, None)); // an octave up
+        assert_eq!(Zones::Steps(8).pitch(128, 1.0, &key), (128, None)); // no note
+
+        let (note, bend) = Zones::Glide(12).pitch(60, 0.0, &key);
+        assert_eq!(note, 66); // half way, even outside the key
+        assert!(bend.unwrap().abs() < 1e-4);
+        let (note, bend) = Zones::Glide(2).pitch(60, -0.2, &key);
+        assert_eq!(note, 61);
+        assert!((bend.unwrap() + 0.2).abs() < 1e-4);
+        assert_eq!(Zones::Glide(12).pitch(120, 1.0, &key), (127, Some(0.0)));
+    }
+}